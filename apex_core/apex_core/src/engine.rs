@@ -1,157 +0,0 @@
-//! Core AGI Engine - ericadamsai watermark
-//! Implements the fundamental execution engine for the Apex AGI system
-
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use tokio::task::JoinHandle;
-use tracing::{info, debug, warn};
-use serde::{Deserialize, Serialize};
-
-/// Core execution engine for AGI tasks
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ApeXEngine {
-    pub id: String,
-    pub version: String,
-    pub state: Arc<Mutex<EngineState>>,
-    pub capabilities: Vec<String>,
-}
-
-/// Engine execution state
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct EngineState {
-    pub status: ExecutionStatus,
-    pub tasks: HashMap<String, TaskMetadata>,
-    pub metrics: ExecutionMetrics,
-}
-
-/// Execution status enum
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum ExecutionStatus {
-    Idle,
-    Running,
-    Paused,
-    Error(String),
-}
-
-/// Task metadata
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct TaskMetadata {
-    pub task_id: String,
-    pub description: String,
-    pub created_at: String,
-    pub status: TaskStatus,
-    pub priority: u32,
-}
-
-/// Task execution status
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum TaskStatus {
-    Pending,
-    Running,
-    Completed,
-    Failed(String),
-}
-
-/// Execution metrics
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ExecutionMetrics {
-    pub total_tasks: u64,
-    pub completed_tasks: u64,
-    pub failed_tasks: u64,
-    pub total_latency_ms: u64,
-}
-
-impl ApeXEngine {
-    /// Create a new AGI engine instance
-    pub fn new(id: String) -> Self {
-        info!("[ericadamsai] Initializing ApeX Engine: {}", id);
-        Self {
-            id: id.clone(),
-            version: "0.1.0-alpha".to_string(),
-            state: Arc::new(Mutex::new(EngineState {
-                status: ExecutionStatus::Idle,
-                tasks: HashMap::new(),
-                metrics: ExecutionMetrics {
-                    total_tasks: 0,
-                    completed_tasks: 0,
-                    failed_tasks: 0,
-                    total_latency_ms: 0,
-                },
-            })),
-            capabilities: vec![
-                "task_execution".to_string(),
-                "reasoning".to_string(),
-                "planning".to_string(),
-                "optimization".to_string(),
-            ],
-        }
-    }
-
-    /// Execute a task asynchronously
-    pub async fn execute_task(&self, task_id: String, description: String) -> Result<String, String> {
-        debug!("[ericadamsai] Executing task: {}", task_id);
-        
-        let mut state = self.state.lock().unwrap();
-        
-        state.tasks.insert(
-            task_id.clone(),
-            TaskMetadata {
-                task_id: task_id.clone(),
-                description,
-                created_at: chrono::Local::now().to_rfc3339(),
-                status: TaskStatus::Running,
-                priority: 1,
-            },
-        );
-        
-        state.status = ExecutionStatus::Running;
-        state.metrics.total_tasks += 1;
-        
-        drop(state);
-        
-        // Simulate task execution
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        
-        let mut state = self.state.lock().unwrap();
-        if let Some(task) = state.tasks.get_mut(&task_id) {
-            task.status = TaskStatus::Completed;
-        }
-        state.metrics.completed_tasks += 1;
-        state.status = ExecutionStatus::Idle;
-        
-        info!("[ericadamsai] Task completed: {}", task_id);
-        Ok(format!("Task {} completed successfully", task_id))
-    }
-
-    /// Get current engine state
-    pub fn get_state(&self) -> EngineState {
-        self.state.lock().unwrap().clone()
-    }
-
-    /// Reset engine state
-    pub fn reset(&self) {
-        let mut state = self.state.lock().unwrap();
-        state.status = ExecutionStatus::Idle;
-        state.tasks.clear();
-        info!("[ericadamsai] Engine state reset");
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_engine_creation() {
-        let engine = ApeXEngine::new("test-engine".to_string());
-        assert_eq!(engine.id, "test-engine");
-        assert_eq!(engine.capabilities.len(), 4);
-    }
-
-    #[tokio::test]
-    async fn test_task_execution() {
-        let engine = ApeXEngine::new("test-engine".to_string());
-        let result = engine.execute_task("task-1".to_string(), "Test task".to_string()).await;
-        assert!(result.is_ok());
-    }
-}
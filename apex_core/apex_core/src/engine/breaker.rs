@@ -0,0 +1,222 @@
+//! Circuit Breaker - ericadamsai watermark
+//! Stops calling executors that keep failing until a cool-down has passed
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Circuit breaker state
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls flow normally
+    Closed,
+    /// Calls are rejected until the cool-down expires
+    Open,
+    /// A limited number of probe calls decide whether to close again
+    HalfOpen,
+}
+
+impl BreakerState {
+    /// Lowercase name used in telemetry keys
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+
+    /// Numeric value reported as a telemetry gauge
+    pub fn as_gauge(&self) -> f64 {
+        match self {
+            BreakerState::Closed => 0.0,
+            BreakerState::HalfOpen => 1.0,
+            BreakerState::Open => 2.0,
+        }
+    }
+}
+
+/// Circuit breaker configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the breaker
+    pub failure_threshold: u32,
+    /// Time an open breaker rejects calls before allowing probes
+    pub cooldown_ms: u64,
+    /// Concurrent probe calls allowed while half-open
+    pub half_open_max_calls: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown_ms: 30_000,
+            half_open_max_calls: 1,
+        }
+    }
+}
+
+/// State change of a breaker
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakerTransition {
+    pub name: String,
+    pub from: BreakerState,
+    pub to: BreakerState,
+}
+
+/// Circuit breaker guarding one executor
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitBreaker {
+    pub name: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub half_open_calls: u32,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            half_open_calls: 0,
+        }
+    }
+
+    /// Ask to make a call
+    ///
+    /// Returns whether the call may proceed, plus the transition to
+    /// half-open if the cool-down has just expired.
+    pub fn try_acquire(
+        &mut self,
+        now: DateTime<Utc>,
+        config: &CircuitBreakerConfig,
+    ) -> (bool, Option<BreakerTransition>) {
+        match self.state {
+            BreakerState::Closed => (true, None),
+            BreakerState::Open => {
+                let cooled_down = self
+                    .opened_at
+                    .map(|opened| (now - opened).num_milliseconds() >= config.cooldown_ms as i64)
+                    .unwrap_or(true);
+                if !cooled_down {
+                    return (false, None);
+                }
+                let transition = self.transition(BreakerState::HalfOpen);
+                self.half_open_calls = 1;
+                (true, transition)
+            }
+            BreakerState::HalfOpen => {
+                if self.half_open_calls >= config.half_open_max_calls.max(1) {
+                    return (false, None);
+                }
+                self.half_open_calls += 1;
+                (true, None)
+            }
+        }
+    }
+
+    /// Record a successful call
+    pub fn record_success(&mut self) -> Option<BreakerTransition> {
+        self.consecutive_failures = 0;
+        match self.state {
+            BreakerState::HalfOpen => {
+                self.half_open_calls = 0;
+                self.opened_at = None;
+                self.transition(BreakerState::Closed)
+            }
+            _ => None,
+        }
+    }
+
+    /// Record a failed call
+    pub fn record_failure(
+        &mut self,
+        now: DateTime<Utc>,
+        config: &CircuitBreakerConfig,
+    ) -> Option<BreakerTransition> {
+        self.consecutive_failures += 1;
+        match self.state {
+            BreakerState::HalfOpen => self.open(now),
+            BreakerState::Closed if self.consecutive_failures >= config.failure_threshold.max(1) => {
+                self.open(now)
+            }
+            _ => None,
+        }
+    }
+
+    /// Release an acquired call whose outcome says nothing about the
+    /// executor's health, such as a cancellation
+    pub fn release(&mut self) {
+        if self.state == BreakerState::HalfOpen {
+            self.half_open_calls = self.half_open_calls.saturating_sub(1);
+        }
+    }
+
+    fn open(&mut self, now: DateTime<Utc>) -> Option<BreakerTransition> {
+        self.opened_at = Some(now);
+        self.half_open_calls = 0;
+        self.transition(BreakerState::Open)
+    }
+
+    fn transition(&mut self, to: BreakerState) -> Option<BreakerTransition> {
+        let from = self.state;
+        if from == to {
+            return None;
+        }
+        self.state = to;
+        Some(BreakerTransition {
+            name: self.name.clone(),
+            from,
+            to,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_breaker_cycle() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown_ms: 1000,
+            half_open_max_calls: 1,
+        };
+        let start = Utc::now();
+        let mut breaker = CircuitBreaker::new("search");
+
+        assert!(breaker.record_failure(start, &config).is_none());
+        let opened = breaker.record_failure(start, &config).unwrap();
+        assert_eq!((opened.from, opened.to), (BreakerState::Closed, BreakerState::Open));
+        assert_eq!(breaker.try_acquire(start + Duration::milliseconds(500), &config), (false, None));
+
+        let (allowed, transition) = breaker.try_acquire(start + Duration::milliseconds(1000), &config);
+        assert!(allowed);
+        assert_eq!(transition.unwrap().to, BreakerState::HalfOpen);
+        assert!(!breaker.try_acquire(start + Duration::milliseconds(1000), &config).0);
+
+        let closed = breaker.record_success().unwrap();
+        assert_eq!(closed.to, BreakerState::Closed);
+        assert_eq!(breaker.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown_ms: 0,
+            half_open_max_calls: 1,
+        };
+        let now = Utc::now();
+        let mut breaker = CircuitBreaker::new("search");
+        breaker.record_failure(now, &config);
+        assert!(breaker.try_acquire(now, &config).0);
+        let reopened = breaker.record_failure(now, &config).unwrap();
+        assert_eq!((reopened.from, reopened.to), (BreakerState::HalfOpen, BreakerState::Open));
+    }
+}
@@ -0,0 +1,193 @@
+//! Engine Events - ericadamsai watermark
+//! Broadcast stream of task lifecycle and engine status changes
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tracing::warn;
+
+use super::ExecutionStatus;
+
+/// Lifecycle event published by the engine
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum EngineEvent {
+    TaskSubmitted { task_id: String, kind: String, priority: u32 },
+    TaskStarted { task_id: String, attempt: u32 },
+    TaskRetried { task_id: String, attempt: u32, delay_ms: u64, error: String },
+    TaskCompleted { task_id: String, latency_ms: u64 },
+    TaskFailed { task_id: String, error: String },
+    TaskCancelled { task_id: String },
+    TaskTimedOut { task_id: String, timeout_ms: u64 },
+    StatusChanged { from: ExecutionStatus, to: ExecutionStatus },
+}
+
+/// Kind of an engine event, used for filtering
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    TaskSubmitted,
+    TaskStarted,
+    TaskRetried,
+    TaskCompleted,
+    TaskFailed,
+    TaskCancelled,
+    TaskTimedOut,
+    StatusChanged,
+}
+
+impl EngineEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            EngineEvent::TaskSubmitted { .. } => EventKind::TaskSubmitted,
+            EngineEvent::TaskStarted { .. } => EventKind::TaskStarted,
+            EngineEvent::TaskRetried { .. } => EventKind::TaskRetried,
+            EngineEvent::TaskCompleted { .. } => EventKind::TaskCompleted,
+            EngineEvent::TaskFailed { .. } => EventKind::TaskFailed,
+            EngineEvent::TaskCancelled { .. } => EventKind::TaskCancelled,
+            EngineEvent::TaskTimedOut { .. } => EventKind::TaskTimedOut,
+            EngineEvent::StatusChanged { .. } => EventKind::StatusChanged,
+        }
+    }
+
+    /// Task the event belongs to; `None` for engine-wide events
+    pub fn task_id(&self) -> Option<&str> {
+        match self {
+            EngineEvent::TaskSubmitted { task_id, .. }
+            | EngineEvent::TaskStarted { task_id, .. }
+            | EngineEvent::TaskRetried { task_id, .. }
+            | EngineEvent::TaskCompleted { task_id, .. }
+            | EngineEvent::TaskFailed { task_id, .. }
+            | EngineEvent::TaskCancelled { task_id }
+            | EngineEvent::TaskTimedOut { task_id, .. } => Some(task_id),
+            EngineEvent::StatusChanged { .. } => None,
+        }
+    }
+}
+
+/// Selects which events a subscriber receives
+///
+/// An unset field matches everything. A task id filter drops engine-wide
+/// events such as status changes.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    pub task_ids: Option<HashSet<String>>,
+    pub kinds: Option<HashSet<EventKind>>,
+}
+
+impl EventFilter {
+    /// Filter that accepts every event
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Filter that accepts events for one task
+    pub fn task(task_id: &str) -> Self {
+        Self {
+            task_ids: Some(HashSet::from([task_id.to_string()])),
+            kinds: None,
+        }
+    }
+
+    /// Restrict the filter to the given event kinds
+    pub fn kinds(mut self, kinds: &[EventKind]) -> Self {
+        self.kinds = Some(kinds.iter().copied().collect());
+        self
+    }
+
+    pub fn matches(&self, event: &EngineEvent) -> bool {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&event.kind()) {
+                return false;
+            }
+        }
+        match (&self.task_ids, event.task_id()) {
+            (None, _) => true,
+            (Some(ids), Some(task_id)) => ids.contains(task_id),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Filtered receiver of engine events
+///
+/// Events are buffered per subscriber up to the engine's configured
+/// capacity. A subscriber that falls further behind skips the oldest
+/// events instead of blocking the engine.
+pub struct EventSubscription {
+    receiver: broadcast::Receiver<EngineEvent>,
+    filter: EventFilter,
+}
+
+impl EventSubscription {
+    pub(crate) fn new(receiver: broadcast::Receiver<EngineEvent>, filter: EventFilter) -> Self {
+        Self { receiver, filter }
+    }
+
+    /// Wait for the next matching event; `None` once the engine is dropped
+    pub async fn recv(&mut self) -> Option<EngineEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(event),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("[ericadamsai] Event subscriber lagged, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Return the next matching event if one is already buffered
+    pub fn try_recv(&mut self) -> Option<EngineEvent> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) if self.filter.matches(&event) => return Some(event),
+                Ok(_) => continue,
+                Err(TryRecvError::Lagged(skipped)) => {
+                    warn!("[ericadamsai] Event subscriber lagged, skipped {} events", skipped);
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_filter() {
+        let started = EngineEvent::TaskStarted { task_id: "task-1".to_string(), attempt: 1 };
+        let other = EngineEvent::TaskStarted { task_id: "task-2".to_string(), attempt: 1 };
+        let status = EngineEvent::StatusChanged {
+            from: ExecutionStatus::Idle,
+            to: ExecutionStatus::Running,
+        };
+
+        assert!(EventFilter::all().matches(&status));
+        let filter = EventFilter::task("task-1");
+        assert!(filter.matches(&started));
+        assert!(!filter.matches(&other));
+        assert!(!filter.matches(&status));
+
+        let filter = EventFilter::all().kinds(&[EventKind::StatusChanged]);
+        assert!(filter.matches(&status));
+        assert!(!filter.matches(&started));
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_skips_oldest() {
+        let (sender, receiver) = broadcast::channel(2);
+        let mut subscription = EventSubscription::new(receiver, EventFilter::all());
+        for attempt in 1..=4 {
+            sender
+                .send(EngineEvent::TaskStarted { task_id: "task-1".to_string(), attempt })
+                .unwrap();
+        }
+        assert_eq!(
+            subscription.recv().await,
+            Some(EngineEvent::TaskStarted { task_id: "task-1".to_string(), attempt: 3 })
+        );
+    }
+}
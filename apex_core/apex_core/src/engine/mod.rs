@@ -0,0 +1,1597 @@
+//! Core AGI Engine - ericadamsai watermark
+//! Implements the fundamental execution engine for the Apex AGI system
+
+pub mod breaker;
+pub mod events;
+pub mod queue;
+pub mod retry;
+pub mod snapshot;
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use async_trait::async_trait;
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use tracing::{info, debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::persist::DataStore;
+use crate::telemetry::TelemetryCollector;
+
+pub use breaker::{BreakerState, BreakerTransition, CircuitBreaker, CircuitBreakerConfig};
+pub use events::{EngineEvent, EventFilter, EventKind, EventSubscription};
+pub use queue::{AgingPolicy, QueuedTask, TaskQueue};
+pub use retry::{RetryPolicy, RetryPredicate};
+pub use snapshot::{RecoveryPolicy, SnapshotConfig};
+
+/// Number of recent task latencies used for the rolling percentiles
+const LATENCY_WINDOW: usize = 1024;
+
+/// Output produced by a task executor
+pub type TaskOutput = serde_json::Value;
+
+/// Error produced while executing a task
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, thiserror::Error)]
+pub enum TaskError {
+    #[error("no executor registered for task kind: {0}")]
+    NoExecutor(String),
+    #[error("task execution failed: {0}")]
+    Failed(String),
+    #[error("task was cancelled")]
+    Cancelled,
+    #[error("task timed out after {0}ms")]
+    TimedOut(u64),
+    #[error("circuit breaker open for executor: {0}")]
+    CircuitOpen(String),
+    #[error("a task with id {0} is already pending or running")]
+    DuplicateTask(String),
+}
+
+impl TaskError {
+    /// Whether the default retry policy treats this error as transient
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, TaskError::Failed(_) | TaskError::TimedOut(_))
+    }
+}
+
+/// Pluggable executor for a kind of task
+///
+/// Executors are registered with the engine by task kind and receive every
+/// task submitted with that kind.
+#[async_trait]
+pub trait TaskExecutor: Send + Sync {
+    /// Execute a single task and return its output
+    async fn execute(&self, task: &TaskSpec) -> Result<TaskOutput, TaskError>;
+}
+
+/// Engine configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Maximum number of tasks executing at the same time
+    pub max_concurrency: usize,
+    /// Priority aging applied to queued tasks
+    pub aging: AgingPolicy,
+    /// Deadline for tasks that do not set their own
+    pub default_timeout_ms: Option<u64>,
+    /// Retry policy for tasks that do not set their own
+    pub retry: RetryPolicy,
+    /// Per-executor circuit breaker; `None` disables breakers
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Retention of finished tasks in memory
+    pub history: HistoryConfig,
+    /// Events buffered per subscriber before the oldest are dropped
+    pub event_capacity: usize,
+    /// When engine state snapshots are written to the data store
+    pub snapshot: SnapshotConfig,
+    /// What recovery does with tasks that were running at snapshot time
+    pub recovery: RecoveryPolicy,
+}
+
+/// Retention limits for finished tasks kept in `EngineState.tasks`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Maximum number of finished tasks kept in memory
+    pub max_entries: Option<usize>,
+    /// Maximum time a finished task is kept in memory
+    pub max_age_ms: Option<u64>,
+    /// Archive evicted tasks to the engine's data store, if one is set
+    pub archive: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(10_000),
+            max_age_ms: None,
+            archive: true,
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 8,
+            aging: AgingPolicy::default(),
+            default_timeout_ms: None,
+            retry: RetryPolicy::none(),
+            circuit_breaker: Some(CircuitBreakerConfig::default()),
+            history: HistoryConfig::default(),
+            event_capacity: 1024,
+            snapshot: SnapshotConfig::default(),
+            recovery: RecoveryPolicy::default(),
+        }
+    }
+}
+
+/// Core execution engine for AGI tasks
+#[derive(Clone)]
+pub struct ApeXEngine {
+    pub id: String,
+    pub version: String,
+    pub config: EngineConfig,
+    pub state: Arc<Mutex<EngineState>>,
+    pub capabilities: Vec<String>,
+    executors: Arc<RwLock<HashMap<String, Arc<dyn TaskExecutor>>>>,
+    signals: Arc<Mutex<HashMap<String, TaskSignals>>>,
+    telemetry: Arc<TelemetryCollector>,
+    store: Option<Arc<DataStore>>,
+    events: broadcast::Sender<EngineEvent>,
+}
+
+/// Wakeups for one pending or running task
+#[derive(Clone, Default)]
+struct TaskSignals {
+    /// Sequence number of the task's queue entry, telling this submission
+    /// apart from a later one that reuses the task id
+    token: u64,
+    /// Woken when the task reaches the head of the queue with a worker slot
+    /// free, or is cancelled while queued
+    dispatch: Arc<Notify>,
+    /// Woken to cancel the task while it runs
+    cancel: Arc<Notify>,
+}
+
+/// Engine execution state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EngineState {
+    pub status: ExecutionStatus,
+    pub tasks: HashMap<String, TaskMetadata>,
+    pub metrics: ExecutionMetrics,
+    /// Number of tasks currently holding a worker slot
+    pub running_tasks: usize,
+    /// Tasks waiting for a worker slot
+    pub queue: TaskQueue,
+    /// Whether dispatch of queued tasks is suspended
+    pub paused: bool,
+    /// Circuit breakers keyed by task kind
+    pub breakers: HashMap<String, CircuitBreaker>,
+    /// Finished task ids, oldest first
+    pub history: VecDeque<String>,
+    /// Submission parameters of pending and running tasks, kept so they
+    /// can be re-queued after a restart
+    pub in_flight: HashMap<String, TaskSpec>,
+}
+
+/// Execution status enum
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ExecutionStatus {
+    Idle,
+    Running,
+    Paused,
+    Error(String),
+}
+
+/// Task submission parameters
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskSpec {
+    pub task_id: String,
+    pub kind: String,
+    pub description: String,
+    pub input: serde_json::Value,
+    /// Scheduling priority; higher values are dispatched first
+    pub priority: u32,
+    /// Deadline counted from dispatch, covering every attempt and the
+    /// backoff between them; overrides the engine default
+    pub timeout_ms: Option<u64>,
+    /// Retry policy, overriding the engine default
+    pub retry: Option<RetryPolicy>,
+}
+
+/// Task metadata
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskMetadata {
+    pub task_id: String,
+    pub kind: String,
+    pub description: String,
+    pub created_at: String,
+    pub status: TaskStatus,
+    pub priority: u32,
+    pub output: Option<TaskOutput>,
+    pub attempts: Vec<TaskAttempt>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// Time spent in the scheduler queue
+    pub queue_wait_ms: Option<u64>,
+    /// Time from dispatch to the final outcome, including retries
+    pub run_ms: Option<u64>,
+}
+
+/// A single run of a task
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TaskAttempt {
+    pub attempt: u32,
+    pub started_at: String,
+    pub finished_at: String,
+    pub error: Option<String>,
+}
+
+/// Task execution status
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum TaskStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed(String),
+    Cancelled,
+    TimedOut,
+    /// The task was running when the engine stopped
+    Interrupted,
+}
+
+/// Execution metrics
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionMetrics {
+    pub total_tasks: u64,
+    pub completed_tasks: u64,
+    pub failed_tasks: u64,
+    pub cancelled_tasks: u64,
+    pub timed_out_tasks: u64,
+    /// Tasks found running on recovery and not re-queued
+    #[serde(default)]
+    pub interrupted_tasks: u64,
+    /// Sum of queue wait and run time over completed, failed and timed
+    /// out tasks; cancelled tasks are left out of the latency figures
+    pub total_latency_ms: u64,
+    pub p50_latency_ms: u64,
+    pub p95_latency_ms: u64,
+    pub p99_latency_ms: u64,
+    /// Most recent task latencies, oldest first
+    pub recent_latencies_ms: VecDeque<u64>,
+}
+
+impl TaskSpec {
+    /// Create a task spec with no input and default priority
+    pub fn new(task_id: String, kind: String, description: String) -> Self {
+        Self {
+            task_id,
+            kind,
+            description,
+            input: serde_json::Value::Null,
+            priority: 1,
+            timeout_ms: None,
+            retry: None,
+        }
+    }
+}
+
+impl TaskMetadata {
+    /// Parsed `finished_at` timestamp, if the task has finished
+    pub fn finished_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let timestamp = self.finished_at.as_deref()?;
+        chrono::DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&chrono::Utc))
+    }
+}
+
+impl ExecutionMetrics {
+    /// Record a finished task's end-to-end latency and refresh the percentiles
+    fn record_latency(&mut self, latency_ms: u64) {
+        self.total_latency_ms += latency_ms;
+        if self.recent_latencies_ms.len() == LATENCY_WINDOW {
+            self.recent_latencies_ms.pop_front();
+        }
+        self.recent_latencies_ms.push_back(latency_ms);
+
+        let mut sorted: Vec<u64> = self.recent_latencies_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        self.p50_latency_ms = percentile(&sorted, 50.0);
+        self.p95_latency_ms = percentile(&sorted, 95.0);
+        self.p99_latency_ms = percentile(&sorted, 99.0);
+    }
+
+    /// Mean end-to-end latency of completed, failed and timed out tasks
+    pub fn average_latency_ms(&self) -> f64 {
+        let finished = self.completed_tasks + self.failed_tasks + self.timed_out_tasks;
+        if finished == 0 {
+            0.0
+        } else {
+            self.total_latency_ms as f64 / finished as f64
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl TaskStatus {
+    /// Whether the task has reached a final state
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, TaskStatus::Pending | TaskStatus::Running)
+    }
+}
+
+impl EngineState {
+    /// Number of tasks waiting for a worker slot
+    pub fn queue_depth(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Queued task ids in the order they would be dispatched now
+    pub fn queue_order(&self) -> Vec<String> {
+        self.queue.order(chrono::Utc::now())
+    }
+
+    /// Drop finished tasks beyond the history limits and return them,
+    /// oldest first
+    fn evict_history(&mut self, config: &HistoryConfig, now: chrono::DateTime<chrono::Utc>) -> Vec<TaskMetadata> {
+        let mut evicted = Vec::new();
+        while let Some(task_id) = self.history.front() {
+            let over_count = config
+                .max_entries
+                .map(|max| self.history.len() > max)
+                .unwrap_or(false);
+            let over_age = match (config.max_age_ms, self.tasks.get(task_id)) {
+                (Some(max_age_ms), Some(task)) => task
+                    .finished_time()
+                    .map(|finished| (now - finished).num_milliseconds() > max_age_ms as i64)
+                    .unwrap_or(false),
+                _ => false,
+            };
+            if !over_count && !over_age {
+                break;
+            }
+            let task_id = self.history.pop_front().unwrap();
+            if let Some(task) = self.tasks.remove(&task_id) {
+                evicted.push(task);
+            }
+        }
+        evicted
+    }
+
+    /// Recompute the aggregate status from the pause flag and running task
+    /// count, returning a status change event if it changed
+    fn refresh_status(&mut self) -> Option<EngineEvent> {
+        if matches!(self.status, ExecutionStatus::Error(_)) {
+            return None;
+        }
+        let status = if self.paused {
+            ExecutionStatus::Paused
+        } else if self.running_tasks > 0 {
+            ExecutionStatus::Running
+        } else {
+            ExecutionStatus::Idle
+        };
+        if status == self.status {
+            return None;
+        }
+        let from = std::mem::replace(&mut self.status, status.clone());
+        Some(EngineEvent::StatusChanged { from, to: status })
+    }
+}
+
+impl ApeXEngine {
+    /// Create a new AGI engine instance
+    pub fn new(id: String) -> Self {
+        Self::with_config(id, EngineConfig::default())
+    }
+
+    /// Create a new AGI engine instance with an explicit configuration
+    pub fn with_config(id: String, config: EngineConfig) -> Self {
+        info!("[ericadamsai] Initializing ApeX Engine: {} (max_concurrency={})", id, config.max_concurrency);
+        let queue = TaskQueue::new(config.aging.clone());
+        let (events, _) = broadcast::channel(config.event_capacity.max(1));
+        Self {
+            id: id.clone(),
+            version: "0.1.0-alpha".to_string(),
+            config,
+            state: Arc::new(Mutex::new(EngineState {
+                status: ExecutionStatus::Idle,
+                tasks: HashMap::new(),
+                metrics: ExecutionMetrics {
+                    total_tasks: 0,
+                    completed_tasks: 0,
+                    failed_tasks: 0,
+                    cancelled_tasks: 0,
+                    timed_out_tasks: 0,
+                    interrupted_tasks: 0,
+                    total_latency_ms: 0,
+                    p50_latency_ms: 0,
+                    p95_latency_ms: 0,
+                    p99_latency_ms: 0,
+                    recent_latencies_ms: VecDeque::new(),
+                },
+                running_tasks: 0,
+                queue,
+                paused: false,
+                breakers: HashMap::new(),
+                history: VecDeque::new(),
+                in_flight: HashMap::new(),
+            })),
+            capabilities: vec![
+                "task_execution".to_string(),
+                "reasoning".to_string(),
+                "planning".to_string(),
+                "optimization".to_string(),
+            ],
+            executors: Arc::new(RwLock::new(HashMap::new())),
+            signals: Arc::new(Mutex::new(HashMap::new())),
+            telemetry: Arc::new(TelemetryCollector::new()),
+            store: None,
+            events,
+        }
+    }
+
+    /// Subscribe to engine events matching a filter
+    pub fn subscribe(&self, filter: EventFilter) -> EventSubscription {
+        EventSubscription::new(self.events.subscribe(), filter)
+    }
+
+    /// Publish an event to current subscribers without waiting on them
+    fn emit(&self, event: EngineEvent) {
+        // Sending only fails when nobody is subscribed.
+        let _ = self.events.send(event);
+    }
+
+    fn emit_all(&self, events: impl IntoIterator<Item = Option<EngineEvent>>) {
+        for event in events.into_iter().flatten() {
+            self.emit(event);
+        }
+    }
+
+    /// Archive evicted tasks to a data store
+    pub fn with_store(mut self, store: Arc<DataStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Report engine telemetry to a shared collector
+    pub fn with_telemetry(mut self, telemetry: Arc<TelemetryCollector>) -> Self {
+        self.telemetry = telemetry;
+        self
+    }
+
+    /// Telemetry collector used by this engine
+    pub fn telemetry(&self) -> Arc<TelemetryCollector> {
+        self.telemetry.clone()
+    }
+
+    /// Register an executor for a task kind, replacing any previous one
+    pub fn register_executor(&self, kind: &str, executor: Arc<dyn TaskExecutor>) {
+        let mut executors = self.executors.write().unwrap();
+        if executors.insert(kind.to_string(), executor).is_some() {
+            warn!("[ericadamsai] Replacing executor for task kind: {}", kind);
+        }
+        info!("[ericadamsai] Registered executor for task kind: {}", kind);
+    }
+
+    /// Remove the executor registered for a task kind
+    pub fn unregister_executor(&self, kind: &str) -> bool {
+        self.executors.write().unwrap().remove(kind).is_some()
+    }
+
+    /// List the task kinds that have a registered executor
+    pub fn executor_kinds(&self) -> Vec<String> {
+        let mut kinds: Vec<String> = self.executors.read().unwrap().keys().cloned().collect();
+        kinds.sort();
+        kinds
+    }
+
+    /// Execute a task asynchronously
+    ///
+    /// Fails with [`TaskError::DuplicateTask`] if a task with the same id is
+    /// still pending or running. The task waits as `Pending` in the scheduler
+    /// queue until it is the highest-priority queued task and a worker slot
+    /// is free, then runs on the executor registered for its kind.
+    ///
+    /// A running task is abandoned at its next await point when it passes
+    /// its deadline or is cancelled with [`ApeXEngine::cancel_task`]. Failed
+    /// attempts are retried according to the task's retry policy while the
+    /// task keeps its worker slot, but never past the deadline.
+    pub async fn execute_task(&self, task: TaskSpec) -> Result<TaskOutput, TaskError> {
+        debug!("[ericadamsai] Submitting task: {} ({})", task.task_id, task.kind);
+        let signals = self.register_task(&task)?;
+        self.run_queued_task(task, signals).await
+    }
+
+    /// Wait for a registered task to be dispatched, then run it to its
+    /// final outcome
+    ///
+    /// If the returned future is dropped first, the task is settled as
+    /// cancelled so it neither blocks the queue nor keeps its worker slot.
+    async fn run_queued_task(&self, task: TaskSpec, signals: TaskSignals) -> Result<TaskOutput, TaskError> {
+        let mut guard = TaskGuard {
+            engine: self,
+            task: &task,
+            token: signals.token,
+            dispatched: None,
+            in_attempt: false,
+            settled: false,
+        };
+        if let Err(e) = self.wait_for_dispatch(&task.task_id, &signals).await {
+            guard.settled = true;
+            self.release_signals(&task.task_id, signals.token);
+            return Err(e);
+        }
+        let dispatched = Instant::now();
+        guard.dispatched = Some(dispatched);
+
+        let policy = task.retry.as_ref().unwrap_or(&self.config.retry);
+        let deadline = task
+            .timeout_ms
+            .or(self.config.default_timeout_ms)
+            .map(|ms| (dispatched + Duration::from_millis(ms), ms));
+        let mut attempt = 1;
+
+        let result = loop {
+            let started_at = chrono::Local::now().to_rfc3339();
+            self.emit(EngineEvent::TaskStarted { task_id: task.task_id.clone(), attempt });
+            let result = if self.acquire_breaker(&task.kind) {
+                guard.in_attempt = true;
+                let result = tokio::select! {
+                    result = Self::run_until(self.run_attempt(&task), deadline) => result,
+                    _ = signals.cancel.notified() => Err(TaskError::Cancelled),
+                };
+                self.record_breaker(&task.kind, &result);
+                guard.in_attempt = false;
+                result
+            } else {
+                Err(TaskError::CircuitOpen(task.kind.clone()))
+            };
+            self.record_attempt(&task.task_id, attempt, started_at, &result);
+
+            match result {
+                Err(e) if policy.should_retry(attempt, &e) && !Self::is_past(deadline) => {
+                    let delay_ms = policy.delay_ms(attempt);
+                    warn!(
+                        "[ericadamsai] Task {} attempt {} failed, retrying in {}ms: {}",
+                        task.task_id, attempt, delay_ms, e
+                    );
+                    self.emit(EngineEvent::TaskRetried {
+                        task_id: task.task_id.clone(),
+                        attempt,
+                        delay_ms,
+                        error: e.to_string(),
+                    });
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_millis(delay_ms)) => {}
+                        ms = Self::wait_for_deadline(deadline) => break Err(TaskError::TimedOut(ms)),
+                        _ = signals.cancel.notified() => break Err(TaskError::Cancelled),
+                    }
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+
+        self.finish_task(&task.task_id, &result, dispatched.elapsed().as_millis() as u64);
+        guard.settled = true;
+        self.prune_history().await;
+        result
+    }
+
+    /// Spawn a task onto the tokio runtime and return a handle to its result
+    pub fn spawn_task(&self, task: TaskSpec) -> JoinHandle<Result<TaskOutput, TaskError>> {
+        let engine = self.clone();
+        tokio::spawn(async move { engine.execute_task(task).await })
+    }
+
+    /// Cancel a pending or running task
+    ///
+    /// Returns `false` if the task is unknown or already finished.
+    pub fn cancel_task(&self, task_id: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        let status = match state.tasks.get(task_id) {
+            Some(meta) => meta.status.clone(),
+            None => return false,
+        };
+        match status {
+            TaskStatus::Pending => self.cancel_queued(state, task_id, None),
+            TaskStatus::Running => {
+                drop(state);
+                let signals = self.signals.lock().unwrap().get(task_id).cloned();
+                match signals {
+                    Some(signals) => {
+                        info!("[ericadamsai] Cancelling running task: {}", task_id);
+                        signals.cancel.notify_one();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Cancel a task while it waits in the queue
+    ///
+    /// With a `token`, only the submission holding that queue entry is
+    /// cancelled, not a later one that reuses the task id.
+    fn cancel_queued(&self, mut state: MutexGuard<'_, EngineState>, task_id: &str, token: Option<u64>) -> bool {
+        match state.queue.get(task_id) {
+            Some(queued) if token.is_none_or(|token| token == queued.sequence) => {}
+            _ => return false,
+        }
+        state.queue.remove(task_id);
+        if let Some(meta) = state.tasks.get_mut(task_id) {
+            meta.status = TaskStatus::Cancelled;
+            meta.finished_at = Some(chrono::Local::now().to_rfc3339());
+        }
+        state.metrics.cancelled_tasks += 1;
+        state.in_flight.remove(task_id);
+        state.history.push_back(task_id.to_string());
+        // Wake the cancelled task's waiter so it returns.
+        if let Some(signals) = self.signals.lock().unwrap().get(task_id) {
+            signals.dispatch.notify_one();
+        }
+        drop(state);
+        info!("[ericadamsai] Cancelled queued task: {}", task_id);
+        self.emit(EngineEvent::TaskCancelled { task_id: task_id.to_string() });
+        self.wake_next();
+        true
+    }
+
+    /// Stop dispatching queued tasks; running tasks continue to completion
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = true;
+        let event = state.refresh_status();
+        drop(state);
+        info!("[ericadamsai] Engine paused: {}", self.id);
+        self.emit_all([event]);
+    }
+
+    /// Resume dispatching queued tasks
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = false;
+        let event = state.refresh_status();
+        drop(state);
+        info!("[ericadamsai] Engine resumed: {}", self.id);
+        self.emit_all([event]);
+        self.wake_next();
+    }
+
+    /// Run one attempt of a task on the executor registered for its kind
+    async fn run_attempt(&self, task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+        let executor = self.executors.read().unwrap().get(&task.kind).cloned();
+        match executor {
+            Some(executor) => executor.execute(task).await,
+            None => Err(TaskError::NoExecutor(task.kind.clone())),
+        }
+    }
+
+    /// Ask the breaker for a task kind whether a call may proceed
+    fn acquire_breaker(&self, kind: &str) -> bool {
+        let config = match &self.config.circuit_breaker {
+            Some(config) => config,
+            None => return true,
+        };
+        let mut state = self.state.lock().unwrap();
+        let breaker = state
+            .breakers
+            .entry(kind.to_string())
+            .or_insert_with(|| CircuitBreaker::new(kind));
+        let (allowed, transition) = breaker.try_acquire(chrono::Utc::now(), config);
+        drop(state);
+
+        if let Some(transition) = transition {
+            self.emit_breaker_transition(&transition);
+        }
+        if !allowed {
+            debug!("[ericadamsai] Circuit open, rejecting call to executor: {}", kind);
+        }
+        allowed
+    }
+
+    /// Feed the outcome of a call into the breaker for its task kind
+    fn record_breaker(&self, kind: &str, result: &Result<TaskOutput, TaskError>) {
+        let config = match &self.config.circuit_breaker {
+            Some(config) => config,
+            None => return,
+        };
+        let mut state = self.state.lock().unwrap();
+        let breaker = match state.breakers.get_mut(kind) {
+            Some(breaker) => breaker,
+            None => return,
+        };
+        let transition = match result {
+            Ok(_) => breaker.record_success(),
+            Err(TaskError::Failed(_)) | Err(TaskError::TimedOut(_)) => {
+                breaker.record_failure(chrono::Utc::now(), config)
+            }
+            Err(_) => {
+                breaker.release();
+                None
+            }
+        };
+        drop(state);
+
+        if let Some(transition) = transition {
+            self.emit_breaker_transition(&transition);
+        }
+    }
+
+    fn emit_breaker_transition(&self, transition: &BreakerTransition) {
+        info!(
+            "[ericadamsai] Circuit breaker {} transitioned {} -> {}",
+            transition.name,
+            transition.from.as_str(),
+            transition.to.as_str()
+        );
+        self.telemetry.increment_counter(
+            &format!("engine.breaker.{}.{}", transition.name, transition.to.as_str()),
+            1,
+        );
+        self.telemetry
+            .set_gauge(&format!("engine.breaker.{}.state", transition.name), transition.to.as_gauge());
+    }
+
+    /// Append the outcome of one attempt to the task's metadata
+    fn record_attempt(
+        &self,
+        task_id: &str,
+        attempt: u32,
+        started_at: String,
+        result: &Result<TaskOutput, TaskError>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(meta) = state.tasks.get_mut(task_id) {
+            meta.attempts.push(TaskAttempt {
+                attempt,
+                started_at,
+                finished_at: chrono::Local::now().to_rfc3339(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+        }
+    }
+
+    /// Await a task future, failing with `TimedOut` once the deadline
+    /// passes
+    ///
+    /// A deadline is the instant it falls on paired with the timeout it was
+    /// derived from, which is what `TimedOut` reports.
+    async fn run_until<F>(run: F, deadline: Option<(Instant, u64)>) -> Result<TaskOutput, TaskError>
+    where
+        F: std::future::Future<Output = Result<TaskOutput, TaskError>>,
+    {
+        match deadline {
+            Some((at, ms)) => tokio::time::timeout_at(at, run)
+                .await
+                .unwrap_or(Err(TaskError::TimedOut(ms))),
+            None => run.await,
+        }
+    }
+
+    /// Wait until the deadline passes and return its timeout; never
+    /// completes without a deadline
+    async fn wait_for_deadline(deadline: Option<(Instant, u64)>) -> u64 {
+        match deadline {
+            Some((at, ms)) => {
+                tokio::time::sleep_until(at).await;
+                ms
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    fn is_past(deadline: Option<(Instant, u64)>) -> bool {
+        deadline.is_some_and(|(at, _)| Instant::now() >= at)
+    }
+
+    /// Record a newly submitted task as pending, queue it and return its
+    /// dispatch and cancellation signals
+    ///
+    /// The id of a finished task may be reused; its old entry is replaced.
+    fn register_task(&self, task: &TaskSpec) -> Result<TaskSignals, TaskError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state.tasks.get(&task.task_id) {
+            if !existing.status.is_terminal() {
+                return Err(TaskError::DuplicateTask(task.task_id.clone()));
+            }
+            state.history.retain(|task_id| task_id != &task.task_id);
+        }
+        let token = state.queue.push(&task.task_id, task.priority, chrono::Utc::now());
+        state.tasks.insert(
+            task.task_id.clone(),
+            TaskMetadata {
+                task_id: task.task_id.clone(),
+                kind: task.kind.clone(),
+                description: task.description.clone(),
+                created_at: chrono::Local::now().to_rfc3339(),
+                status: TaskStatus::Pending,
+                priority: task.priority,
+                output: None,
+                attempts: Vec::new(),
+                started_at: None,
+                finished_at: None,
+                queue_wait_ms: None,
+                run_ms: None,
+            },
+        );
+        state.metrics.total_tasks += 1;
+        state.in_flight.insert(task.task_id.clone(), task.clone());
+        let signals = TaskSignals { token, ..TaskSignals::default() };
+        self.signals.lock().unwrap().insert(task.task_id.clone(), signals.clone());
+        drop(state);
+
+        self.emit(EngineEvent::TaskSubmitted {
+            task_id: task.task_id.clone(),
+            kind: task.kind.clone(),
+            priority: task.priority,
+        });
+        Ok(signals)
+    }
+
+    /// Wait until the scheduler hands this task a worker slot, or fail if
+    /// the task is cancelled while queued
+    ///
+    /// Only the task at the head of the queue is woken when a slot frees
+    /// up. `notify_one` keeps a permit for a waiter that is not parked yet,
+    /// so a wakeup between the check and the await is not lost.
+    async fn wait_for_dispatch(&self, task_id: &str, signals: &TaskSignals) -> Result<(), TaskError> {
+        loop {
+            match self.try_dispatch(task_id, signals.token) {
+                Some(Ok(())) => {
+                    // The next queued task may be able to take a remaining slot.
+                    self.wake_next();
+                    return Ok(());
+                }
+                Some(Err(e)) => return Err(e),
+                None => {
+                    // A slot may be free while another task is ahead of
+                    // this one, e.g. after a higher-priority submission.
+                    self.wake_next();
+                    signals.dispatch.notified().await;
+                }
+            }
+        }
+    }
+
+    /// Drop a task's signals unless a later submission of the same id has
+    /// replaced them
+    fn release_signals(&self, task_id: &str, token: u64) {
+        let mut signals = self.signals.lock().unwrap();
+        if signals.get(task_id).is_some_and(|signals| signals.token == token) {
+            signals.remove(task_id);
+        }
+    }
+
+    /// Wake the task at the head of the queue if it could be dispatched now
+    fn wake_next(&self) {
+        let state = self.state.lock().unwrap();
+        if state.paused || state.running_tasks >= self.config.max_concurrency.max(1) {
+            return;
+        }
+        if let Some(next) = state.queue.peek(chrono::Utc::now()) {
+            if let Some(signals) = self.signals.lock().unwrap().get(&next.task_id) {
+                signals.dispatch.notify_one();
+            }
+        }
+    }
+
+    /// Move a task from the queue to running if it is next in line, the
+    /// engine is not paused and a worker slot is free
+    ///
+    /// Returns `None` while the task has to keep waiting. A queue entry
+    /// with a different `token` belongs to a later submission of the same
+    /// id, so this one was cancelled.
+    fn try_dispatch(&self, task_id: &str, token: u64) -> Option<Result<(), TaskError>> {
+        let mut state = self.state.lock().unwrap();
+        if state.queue.get(task_id).map(|queued| queued.sequence) != Some(token) {
+            return Some(Err(TaskError::Cancelled));
+        }
+        if state.paused || state.running_tasks >= self.config.max_concurrency.max(1) {
+            return None;
+        }
+        let now = chrono::Utc::now();
+        if state.queue.peek(now).map(|next| next.task_id.as_str()) != Some(task_id) {
+            return None;
+        }
+        let queued = state.queue.remove(task_id);
+        let queue_wait_ms = queued
+            .map(|queued| (now - queued.enqueued_at).num_milliseconds().max(0) as u64)
+            .unwrap_or(0);
+        if let Some(meta) = state.tasks.get_mut(task_id) {
+            meta.status = TaskStatus::Running;
+            meta.started_at = Some(chrono::Local::now().to_rfc3339());
+            meta.queue_wait_ms = Some(queue_wait_ms);
+        }
+        state.running_tasks += 1;
+        let event = state.refresh_status();
+        drop(state);
+        self.emit_all([event]);
+        Some(Ok(()))
+    }
+
+    /// Record the outcome and timings of a task and release its slot in the
+    /// aggregate status
+    fn finish_task(&self, task_id: &str, result: &Result<TaskOutput, TaskError>, run_ms: u64) {
+        self.signals.lock().unwrap().remove(task_id);
+        let mut state = self.state.lock().unwrap();
+        let mut queue_wait_ms = 0;
+        if let Some(meta) = state.tasks.get_mut(task_id) {
+            meta.finished_at = Some(chrono::Local::now().to_rfc3339());
+            meta.run_ms = Some(run_ms);
+            queue_wait_ms = meta.queue_wait_ms.unwrap_or(0);
+        }
+        let latency_ms = queue_wait_ms + run_ms;
+        let task_event = match result {
+            Ok(output) => {
+                if let Some(meta) = state.tasks.get_mut(task_id) {
+                    meta.status = TaskStatus::Completed;
+                    meta.output = Some(output.clone());
+                }
+                state.metrics.completed_tasks += 1;
+                info!("[ericadamsai] Task completed: {}", task_id);
+                EngineEvent::TaskCompleted { task_id: task_id.to_string(), latency_ms }
+            }
+            Err(TaskError::Cancelled) => {
+                if let Some(meta) = state.tasks.get_mut(task_id) {
+                    meta.status = TaskStatus::Cancelled;
+                }
+                state.metrics.cancelled_tasks += 1;
+                info!("[ericadamsai] Task cancelled: {}", task_id);
+                EngineEvent::TaskCancelled { task_id: task_id.to_string() }
+            }
+            Err(TaskError::TimedOut(ms)) => {
+                if let Some(meta) = state.tasks.get_mut(task_id) {
+                    meta.status = TaskStatus::TimedOut;
+                }
+                state.metrics.timed_out_tasks += 1;
+                warn!("[ericadamsai] Task timed out after {}ms: {}", ms, task_id);
+                EngineEvent::TaskTimedOut { task_id: task_id.to_string(), timeout_ms: *ms }
+            }
+            Err(e) => {
+                if let Some(meta) = state.tasks.get_mut(task_id) {
+                    meta.status = TaskStatus::Failed(e.to_string());
+                }
+                state.metrics.failed_tasks += 1;
+                warn!("[ericadamsai] Task failed: {}: {}", task_id, e);
+                EngineEvent::TaskFailed { task_id: task_id.to_string(), error: e.to_string() }
+            }
+        };
+        // A cancellation says nothing about how long the task takes to run.
+        let cancelled = matches!(result, Err(TaskError::Cancelled));
+        if !cancelled {
+            state.metrics.record_latency(latency_ms);
+        }
+        let (p50, p95, p99) = (
+            state.metrics.p50_latency_ms,
+            state.metrics.p95_latency_ms,
+            state.metrics.p99_latency_ms,
+        );
+        state.in_flight.remove(task_id);
+        state.history.push_back(task_id.to_string());
+        state.running_tasks = state.running_tasks.saturating_sub(1);
+        let status_event = state.refresh_status();
+        drop(state);
+        self.wake_next();
+        self.emit_all([Some(task_event), status_event]);
+
+        if !cancelled {
+            self.telemetry.record_histogram("engine.task.queue_wait_ms", queue_wait_ms as f64);
+            self.telemetry.record_histogram("engine.task.run_ms", run_ms as f64);
+            self.telemetry.record_histogram("engine.task.latency_ms", latency_ms as f64);
+        }
+        self.telemetry.set_gauge("engine.task.latency_p50_ms", p50 as f64);
+        self.telemetry.set_gauge("engine.task.latency_p95_ms", p95 as f64);
+        self.telemetry.set_gauge("engine.task.latency_p99_ms", p99 as f64);
+    }
+
+    /// Evict finished tasks beyond the history limits, archiving them if
+    /// configured, and return how many were evicted
+    pub async fn prune_history(&self) -> usize {
+        let evicted = {
+            let mut state = self.state.lock().unwrap();
+            state.evict_history(&self.config.history, chrono::Utc::now())
+        };
+        if evicted.is_empty() {
+            return 0;
+        }
+        debug!("[ericadamsai] Evicting {} finished tasks from history", evicted.len());
+        self.archive_tasks(&evicted).await;
+        evicted.len()
+    }
+
+    /// Save tasks dropped from memory to the data store, if archival is
+    /// configured
+    async fn archive_tasks(&self, tasks: &[TaskMetadata]) {
+        if let (true, Some(store)) = (self.config.history.archive, &self.store) {
+            for task in tasks {
+                if let Err(e) = store.save(&self.archive_key(&task.task_id), task).await {
+                    warn!("[ericadamsai] Failed to archive task {}: {}", task.task_id, e);
+                }
+            }
+        }
+    }
+
+    /// Look up a task in memory, falling back to the archive
+    pub async fn get_task(&self, task_id: &str) -> Option<TaskMetadata> {
+        if let Some(task) = self.state.lock().unwrap().tasks.get(task_id) {
+            return Some(task.clone());
+        }
+        let store = self.store.as_ref()?;
+        match store.load::<TaskMetadata>(&self.archive_key(task_id)).await {
+            Ok(task) => Some(task),
+            Err(e) => {
+                debug!("[ericadamsai] Task {} not found in archive: {}", task_id, e);
+                None
+            }
+        }
+    }
+
+    fn archive_key(&self, task_id: &str) -> String {
+        format!("tasks/{}/{}", self.id, task_id)
+    }
+
+    /// Get current engine state
+    pub fn get_state(&self) -> EngineState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Reset engine state
+    ///
+    /// Finished tasks leave memory the same way evicted ones do, so they
+    /// are archived if archival is configured. Tasks that are still pending
+    /// or running keep their entries so their completion can be recorded.
+    pub async fn reset(&self) {
+        let (finished, event) = {
+            let mut state = self.state.lock().unwrap();
+            let (finished, active): (HashMap<_, _>, HashMap<_, _>) = std::mem::take(&mut state.tasks)
+                .into_iter()
+                .partition(|(_, task)| task.status.is_terminal());
+            state.tasks = active;
+            state.history.clear();
+            let previous = std::mem::replace(&mut state.status, ExecutionStatus::Idle);
+            state.refresh_status();
+            let event = (state.status != previous).then(|| EngineEvent::StatusChanged {
+                from: previous,
+                to: state.status.clone(),
+            });
+            (finished, event)
+        };
+        let finished: Vec<TaskMetadata> = finished.into_values().collect();
+        self.archive_tasks(&finished).await;
+        info!("[ericadamsai] Engine state reset, {} finished tasks dropped", finished.len());
+        self.emit_all([event]);
+    }
+}
+
+/// Settles a task whose future is dropped before it finishes
+///
+/// A task still in the queue is cancelled there, and a running task is
+/// finished as cancelled so its worker slot and any breaker probe it held
+/// are released.
+struct TaskGuard<'a> {
+    engine: &'a ApeXEngine,
+    task: &'a TaskSpec,
+    token: u64,
+    dispatched: Option<Instant>,
+    in_attempt: bool,
+    settled: bool,
+}
+
+impl Drop for TaskGuard<'_> {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let task_id = &self.task.task_id;
+        warn!("[ericadamsai] Task future dropped before the task finished: {}", task_id);
+        match self.dispatched {
+            None => {
+                let state = self.engine.state.lock().unwrap();
+                self.engine.cancel_queued(state, task_id, Some(self.token));
+                self.engine.release_signals(task_id, self.token);
+            }
+            Some(dispatched) => {
+                let cancelled = Err(TaskError::Cancelled);
+                if self.in_attempt {
+                    self.engine.record_breaker(&self.task.kind, &cancelled);
+                }
+                self.engine.finish_task(task_id, &cancelled, dispatched.elapsed().as_millis() as u64);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ApeXEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApeXEngine")
+            .field("id", &self.id)
+            .field("version", &self.version)
+            .field("config", &self.config)
+            .field("state", &self.state)
+            .field("capabilities", &self.capabilities)
+            .field("executors", &self.executor_kinds())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoExecutor;
+
+    #[async_trait]
+    impl TaskExecutor for EchoExecutor {
+        async fn execute(&self, task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+            Ok(serde_json::json!({ "echo": task.description }))
+        }
+    }
+
+    struct FailingExecutor;
+
+    #[async_trait]
+    impl TaskExecutor for FailingExecutor {
+        async fn execute(&self, _task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+            Err(TaskError::Failed("tool unavailable".to_string()))
+        }
+    }
+
+    struct SlowExecutor;
+
+    #[async_trait]
+    impl TaskExecutor for SlowExecutor {
+        async fn execute(&self, _task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+            Ok(TaskOutput::Null)
+        }
+    }
+
+    struct RecordingExecutor {
+        order: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl TaskExecutor for RecordingExecutor {
+        async fn execute(&self, task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+            self.order.lock().unwrap().push(task.task_id.clone());
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+            Ok(TaskOutput::Null)
+        }
+    }
+
+    struct FlakyExecutor {
+        failures_left: std::sync::atomic::AtomicU32,
+    }
+
+    #[async_trait]
+    impl TaskExecutor for FlakyExecutor {
+        async fn execute(&self, _task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+            use std::sync::atomic::Ordering;
+            let left = self.failures_left.load(Ordering::SeqCst);
+            if left > 0 {
+                self.failures_left.store(left - 1, Ordering::SeqCst);
+                return Err(TaskError::Failed("transient".to_string()));
+            }
+            Ok(TaskOutput::Bool(true))
+        }
+    }
+
+    #[test]
+    fn test_engine_creation() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        assert_eq!(engine.id, "test-engine");
+        assert_eq!(engine.capabilities.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_task_execution() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+        let result = engine
+            .execute_task(TaskSpec::new("task-1".to_string(), "echo".to_string(), "Test task".to_string()))
+            .await;
+        assert_eq!(result, Ok(serde_json::json!({ "echo": "Test task" })));
+
+        let state = engine.get_state();
+        assert_eq!(state.tasks["task-1"].status, TaskStatus::Completed);
+        assert_eq!(state.metrics.completed_tasks, 1);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_task_ids_are_rejected() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+        engine.pause();
+        let first = engine.spawn_task(TaskSpec::new("task-1".to_string(), "echo".to_string(), "First".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+
+        let duplicate = TaskSpec::new("task-1".to_string(), "echo".to_string(), "Second".to_string());
+        assert_eq!(engine.execute_task(duplicate).await, Err(TaskError::DuplicateTask("task-1".to_string())));
+        assert_eq!(engine.get_state().tasks["task-1"].description, "First");
+
+        engine.resume();
+        assert_eq!(first.await.unwrap(), Ok(serde_json::json!({ "echo": "First" })));
+        let rerun = TaskSpec::new("task-1".to_string(), "echo".to_string(), "Second".to_string());
+        assert!(engine.execute_task(rerun).await.is_ok());
+        let state = engine.get_state();
+        assert_eq!(state.history, VecDeque::from(vec!["task-1".to_string()]));
+        assert_eq!(state.metrics.total_tasks, 2);
+    }
+
+    #[tokio::test]
+    async fn test_task_failure_is_recorded() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        engine.register_executor("flaky", Arc::new(FailingExecutor));
+
+        let result = engine
+            .execute_task(TaskSpec::new("task-1".to_string(), "flaky".to_string(), "Test task".to_string()))
+            .await;
+        assert!(matches!(result, Err(TaskError::Failed(_))));
+
+        let result = engine
+            .execute_task(TaskSpec::new("task-2".to_string(), "unknown".to_string(), "Test task".to_string()))
+            .await;
+        assert_eq!(result, Err(TaskError::NoExecutor("unknown".to_string())));
+
+        let state = engine.get_state();
+        assert!(matches!(state.tasks["task-1"].status, TaskStatus::Failed(_)));
+        assert_eq!(state.metrics.failed_tasks, 2);
+        assert_eq!(state.metrics.completed_tasks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_is_respected() {
+        let config = EngineConfig { max_concurrency: 2, ..EngineConfig::default() };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor("slow", Arc::new(SlowExecutor));
+
+        let handles: Vec<_> = (0..5)
+            .map(|i| engine.spawn_task(TaskSpec::new(format!("task-{}", i), "slow".to_string(), "Slow task".to_string())))
+            .collect();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        let state = engine.get_state();
+        assert_eq!(state.running_tasks, 2);
+        assert_eq!(state.queue_depth(), 3);
+        assert_eq!(state.status, ExecutionStatus::Running);
+
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+        let state = engine.get_state();
+        assert_eq!(state.running_tasks, 0);
+        assert_eq!(state.status, ExecutionStatus::Idle);
+        assert_eq!(state.metrics.completed_tasks, 5);
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_tasks_dispatch_first() {
+        let config = EngineConfig { max_concurrency: 1, ..EngineConfig::default() };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        engine.register_executor("record", Arc::new(RecordingExecutor { order: order.clone() }));
+
+        let blocker = engine.spawn_task(TaskSpec::new("blocker".to_string(), "record".to_string(), "Hold the slot".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+
+        let mut handles = Vec::new();
+        for (id, priority) in [("batch-1", 1), ("batch-2", 1), ("interactive", 10)] {
+            let mut task = TaskSpec::new(id.to_string(), "record".to_string(), "Queued task".to_string());
+            task.priority = priority;
+            handles.push(engine.spawn_task(task));
+            tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+        }
+
+        assert_eq!(engine.get_state().queue_order(), vec!["interactive", "batch-1", "batch-2"]);
+
+        blocker.await.unwrap().unwrap();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["blocker", "interactive", "batch-1", "batch-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_timeout() {
+        let config = EngineConfig { max_concurrency: 1, ..EngineConfig::default() };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor("slow", Arc::new(SlowExecutor));
+
+        let running = engine.spawn_task(TaskSpec::new("running".to_string(), "slow".to_string(), "Slow task".to_string()));
+        let queued = engine.spawn_task(TaskSpec::new("queued".to_string(), "slow".to_string(), "Slow task".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+
+        assert!(engine.cancel_task("queued"));
+        assert!(engine.cancel_task("running"));
+        assert_eq!(running.await.unwrap(), Err(TaskError::Cancelled));
+        assert_eq!(queued.await.unwrap(), Err(TaskError::Cancelled));
+        assert!(!engine.cancel_task("running"));
+
+        let mut task = TaskSpec::new("limited".to_string(), "slow".to_string(), "Slow task".to_string());
+        task.timeout_ms = Some(10);
+        assert_eq!(engine.execute_task(task).await, Err(TaskError::TimedOut(10)));
+
+        let state = engine.get_state();
+        assert_eq!(state.tasks["running"].status, TaskStatus::Cancelled);
+        assert_eq!(state.tasks["queued"].status, TaskStatus::Cancelled);
+        assert_eq!(state.tasks["limited"].status, TaskStatus::TimedOut);
+        assert_eq!(state.metrics.cancelled_tasks, 2);
+        assert_eq!(state.metrics.timed_out_tasks, 1);
+        assert_eq!(state.metrics.failed_tasks, 0);
+        assert_eq!(state.running_tasks, 0);
+        assert_eq!(state.metrics.recent_latencies_ms.len(), 1);
+        assert_eq!(state.metrics.average_latency_ms(), state.metrics.total_latency_ms as f64);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_covers_all_attempts() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        engine.register_executor("failing", Arc::new(FailingExecutor));
+
+        // Every attempt fails at once, but the backoff between them runs
+        // into the deadline.
+        let mut task = TaskSpec::new("retried".to_string(), "failing".to_string(), "Failing task".to_string());
+        task.timeout_ms = Some(80);
+        task.retry = Some(RetryPolicy { initial_backoff_ms: 50, multiplier: 1.0, jitter: 0.0, ..RetryPolicy::exponential(5) });
+        let started = Instant::now();
+        assert_eq!(engine.execute_task(task).await, Err(TaskError::TimedOut(80)));
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert_eq!(engine.get_state().tasks["retried"].attempts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resubmitting_a_cancelled_task_id() {
+        let config = EngineConfig { max_concurrency: 1, ..EngineConfig::default() };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+        engine.pause();
+
+        let stale = engine.spawn_task(TaskSpec::new("x".to_string(), "echo".to_string(), "Old".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+        assert!(engine.cancel_task("x"));
+
+        // Resubmit before the cancelled task's waiter gets to run.
+        let resubmitted = TaskSpec::new("x".to_string(), "echo".to_string(), "New".to_string());
+        let signals = engine.register_task(&resubmitted).unwrap();
+        engine.resume();
+
+        assert_eq!(stale.await.unwrap(), Err(TaskError::Cancelled));
+        assert!(engine.signals.lock().unwrap().contains_key("x"));
+        let result = engine.run_queued_task(resubmitted, signals).await;
+        assert_eq!(result, Ok(serde_json::json!({ "echo": "New" })));
+        assert_eq!(engine.get_state().metrics.cancelled_tasks, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_task_futures_release_queue_and_slot() {
+        let config = EngineConfig { max_concurrency: 1, ..EngineConfig::default() };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor("slow", Arc::new(SlowExecutor));
+        let wait = tokio::time::Duration::from_millis(10);
+
+        let blocker = engine.spawn_task(TaskSpec::new("blocker".to_string(), "slow".to_string(), "Slow task".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+        let pending = engine.execute_task(TaskSpec::new("pending".to_string(), "slow".to_string(), "Slow task".to_string()));
+        assert!(tokio::time::timeout(wait, pending).await.is_err());
+        let state = engine.get_state();
+        assert_eq!(state.tasks["pending"].status, TaskStatus::Cancelled);
+        assert_eq!(state.queue_depth(), 0);
+
+        blocker.await.unwrap().unwrap();
+        let running = engine.execute_task(TaskSpec::new("running".to_string(), "slow".to_string(), "Slow task".to_string()));
+        assert!(tokio::time::timeout(wait, running).await.is_err());
+        let state = engine.get_state();
+        assert_eq!(state.tasks["running"].status, TaskStatus::Cancelled);
+        assert_eq!(state.running_tasks, 0);
+
+        let next = TaskSpec::new("next".to_string(), "slow".to_string(), "Slow task".to_string());
+        assert!(tokio::time::timeout(tokio::time::Duration::from_secs(1), engine.execute_task(next)).await.unwrap().is_ok());
+        assert_eq!(engine.get_state().metrics.cancelled_tasks, 2);
+    }
+
+    #[tokio::test]
+    async fn test_pause_holds_queued_tasks() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+
+        engine.pause();
+        let handle = engine.spawn_task(TaskSpec::new("task-1".to_string(), "echo".to_string(), "Test task".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        let state = engine.get_state();
+        assert_eq!(state.status, ExecutionStatus::Paused);
+        assert_eq!(state.tasks["task-1"].status, TaskStatus::Pending);
+
+        engine.resume();
+        assert!(handle.await.unwrap().is_ok());
+        assert_eq!(engine.get_state().status, ExecutionStatus::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempts_are_retried() {
+        let config = EngineConfig {
+            retry: RetryPolicy { initial_backoff_ms: 1, ..RetryPolicy::exponential(3) },
+            ..EngineConfig::default()
+        };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor(
+            "flaky",
+            Arc::new(FlakyExecutor { failures_left: std::sync::atomic::AtomicU32::new(2) }),
+        );
+
+        let result = engine
+            .execute_task(TaskSpec::new("task-1".to_string(), "flaky".to_string(), "Flaky task".to_string()))
+            .await;
+        assert_eq!(result, Ok(TaskOutput::Bool(true)));
+
+        let mut task = TaskSpec::new("task-2".to_string(), "failing".to_string(), "Failing task".to_string());
+        task.retry = Some(RetryPolicy::exponential(5).retry_on(|_| false));
+        engine.register_executor("failing", Arc::new(FailingExecutor));
+        assert!(engine.execute_task(task).await.is_err());
+
+        let state = engine.get_state();
+        let attempts = &state.tasks["task-1"].attempts;
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts[0].error.is_some());
+        assert_eq!(attempts[2].error, None);
+        assert_eq!(state.tasks["task-2"].attempts.len(), 1);
+        assert_eq!(state.metrics.completed_tasks, 1);
+        assert_eq!(state.metrics.failed_tasks, 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let config = EngineConfig {
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 2,
+                cooldown_ms: 30,
+                half_open_max_calls: 1,
+            }),
+            ..EngineConfig::default()
+        };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor("tool", Arc::new(FailingExecutor));
+
+        for i in 0..2 {
+            let task = TaskSpec::new(format!("fail-{}", i), "tool".to_string(), "Failing task".to_string());
+            assert!(matches!(engine.execute_task(task).await, Err(TaskError::Failed(_))));
+        }
+        let rejected = TaskSpec::new("rejected".to_string(), "tool".to_string(), "Rejected task".to_string());
+        assert_eq!(engine.execute_task(rejected).await, Err(TaskError::CircuitOpen("tool".to_string())));
+        assert_eq!(engine.get_state().breakers["tool"].state, BreakerState::Open);
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(40)).await;
+        engine.register_executor("tool", Arc::new(EchoExecutor));
+        let probe = TaskSpec::new("probe".to_string(), "tool".to_string(), "Probe task".to_string());
+        assert!(engine.execute_task(probe).await.is_ok());
+        assert_eq!(engine.get_state().breakers["tool"].state, BreakerState::Closed);
+
+        let counters = engine.telemetry().get_metrics().counters;
+        assert_eq!(counters.get("engine.breaker.tool.open"), Some(&1));
+        assert_eq!(counters.get("engine.breaker.tool.half_open"), Some(&1));
+        assert_eq!(counters.get("engine.breaker.tool.closed"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_history_is_bounded_and_archived() {
+        let store = crate::persist::memory_store();
+        let config = EngineConfig {
+            history: HistoryConfig { max_entries: Some(2), max_age_ms: None, archive: true },
+            ..EngineConfig::default()
+        };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config).with_store(store);
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+
+        for i in 0..4 {
+            let task = TaskSpec::new(format!("task-{}", i), "echo".to_string(), format!("Task {}", i));
+            engine.execute_task(task).await.unwrap();
+        }
+
+        let state = engine.get_state();
+        assert_eq!(state.tasks.len(), 2);
+        assert_eq!(state.history, VecDeque::from(vec!["task-2".to_string(), "task-3".to_string()]));
+
+        let archived = engine.get_task("task-0").await.unwrap();
+        assert_eq!(archived.status, TaskStatus::Completed);
+        assert_eq!(archived.output, Some(serde_json::json!({ "echo": "Task 0" })));
+        assert!(engine.get_task("missing").await.is_none());
+
+        engine.reset().await;
+        assert!(engine.get_state().tasks.is_empty());
+        assert_eq!(engine.get_task("task-3").await.unwrap().status, TaskStatus::Completed);
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut metrics = ApeXEngine::new("test-engine".to_string()).get_state().metrics;
+        for latency in 1..=100 {
+            metrics.record_latency(latency);
+        }
+        assert_eq!(metrics.total_latency_ms, 5050);
+        assert_eq!(metrics.p50_latency_ms, 50);
+        assert_eq!(metrics.p95_latency_ms, 95);
+        assert_eq!(metrics.p99_latency_ms, 99);
+    }
+
+    #[tokio::test]
+    async fn test_task_timings_are_recorded() {
+        let config = EngineConfig { max_concurrency: 1, ..EngineConfig::default() };
+        let engine = ApeXEngine::with_config("test-engine".to_string(), config);
+        engine.register_executor("slow", Arc::new(SlowExecutor));
+
+        let first = engine.spawn_task(TaskSpec::new("first".to_string(), "slow".to_string(), "Slow task".to_string()));
+        let second = engine.spawn_task(TaskSpec::new("second".to_string(), "slow".to_string(), "Slow task".to_string()));
+        first.await.unwrap().unwrap();
+        second.await.unwrap().unwrap();
+        let cancelled = engine.spawn_task(TaskSpec::new("cancelled".to_string(), "slow".to_string(), "Slow task".to_string()));
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        assert!(engine.cancel_task("cancelled"));
+        assert_eq!(cancelled.await.unwrap(), Err(TaskError::Cancelled));
+
+        let state = engine.get_state();
+        let second = &state.tasks["second"];
+        assert!(second.started_at.is_some());
+        assert!(second.finished_at.is_some());
+        assert!(second.queue_wait_ms.unwrap() >= 40);
+        assert!(second.run_ms.unwrap() >= 50);
+        assert!(state.metrics.total_latency_ms >= 140);
+        assert!(state.metrics.p99_latency_ms >= second.run_ms.unwrap());
+
+        let histograms = engine.telemetry().get_metrics().histograms;
+        // The cancelled run is left out of every timing histogram.
+        assert_eq!(histograms["engine.task.run_ms"].len(), 2);
+        assert_eq!(histograms["engine.task.queue_wait_ms"].len(), 2);
+        assert_eq!(histograms["engine.task.latency_ms"].len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let engine = ApeXEngine::new("test-engine".to_string());
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+        let mut all = engine.subscribe(EventFilter::all());
+        let mut finished = engine.subscribe(
+            EventFilter::task("task-2").kinds(&[EventKind::TaskCompleted, EventKind::TaskFailed]),
+        );
+
+        for id in ["task-1", "task-2"] {
+            let task = TaskSpec::new(id.to_string(), "echo".to_string(), "Test task".to_string());
+            engine.execute_task(task).await.unwrap();
+        }
+
+        let mut kinds = Vec::new();
+        while let Some(event) = all.try_recv() {
+            kinds.push(event.kind());
+        }
+        assert_eq!(
+            &kinds[..5],
+            &[
+                EventKind::TaskSubmitted,
+                EventKind::StatusChanged,
+                EventKind::TaskStarted,
+                EventKind::TaskCompleted,
+                EventKind::StatusChanged,
+            ]
+        );
+        assert_eq!(kinds.len(), 10);
+
+        assert!(matches!(
+            finished.recv().await,
+            Some(EngineEvent::TaskCompleted { task_id, .. }) if task_id == "task-2"
+        ));
+        assert!(finished.try_recv().is_none());
+    }
+}
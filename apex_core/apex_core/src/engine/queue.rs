@@ -0,0 +1,163 @@
+//! Task Queue - ericadamsai watermark
+//! Priority-ordered scheduler queue for pending engine tasks
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Aging policy that raises the priority of tasks the longer they wait
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgingPolicy {
+    /// Waiting time after which a task gains `step` priority levels
+    pub interval_ms: u64,
+    /// Priority levels gained per elapsed interval
+    pub step: u32,
+}
+
+impl Default for AgingPolicy {
+    fn default() -> Self {
+        Self {
+            interval_ms: 1000,
+            step: 1,
+        }
+    }
+}
+
+/// A task waiting for a worker slot
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct QueuedTask {
+    pub task_id: String,
+    pub priority: u32,
+    pub sequence: u64,
+    pub enqueued_at: DateTime<Utc>,
+}
+
+impl QueuedTask {
+    /// Priority including the aging bonus accumulated up to `now`
+    pub fn effective_priority(&self, now: DateTime<Utc>, aging: &AgingPolicy) -> u64 {
+        let waited_ms = (now - self.enqueued_at).num_milliseconds().max(0) as u64;
+        let intervals = waited_ms.checked_div(aging.interval_ms).unwrap_or(0);
+        self.priority as u64 + intervals * aging.step as u64
+    }
+}
+
+/// Pending tasks ordered by effective priority, then submission order
+///
+/// Higher priority values run first. Because aging changes effective
+/// priorities over time, the order is computed on demand rather than kept
+/// in a heap.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TaskQueue {
+    pub aging: AgingPolicy,
+    entries: Vec<QueuedTask>,
+    next_sequence: u64,
+}
+
+impl TaskQueue {
+    /// Create an empty queue with the given aging policy
+    pub fn new(aging: AgingPolicy) -> Self {
+        Self {
+            aging,
+            entries: Vec::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Add a task to the back of its priority class and return its
+    /// sequence number, which no other entry of this queue shares
+    pub fn push(&mut self, task_id: &str, priority: u32, now: DateTime<Utc>) -> u64 {
+        debug!("[ericadamsai] Queueing task {} with priority {}", task_id, priority);
+        let sequence = self.next_sequence;
+        self.entries.push(QueuedTask {
+            task_id: task_id.to_string(),
+            priority,
+            sequence,
+            enqueued_at: now,
+        });
+        self.next_sequence += 1;
+        sequence
+    }
+
+    /// Remove a task from the queue wherever it is
+    pub fn remove(&mut self, task_id: &str) -> Option<QueuedTask> {
+        let index = self.entries.iter().position(|entry| entry.task_id == task_id)?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Task that would be dispatched next
+    pub fn peek(&self, now: DateTime<Utc>) -> Option<&QueuedTask> {
+        self.entries.iter().min_by(|a, b| self.compare(a, b, now))
+    }
+
+    /// Remove and return the task that would be dispatched next
+    pub fn pop(&mut self, now: DateTime<Utc>) -> Option<QueuedTask> {
+        let task_id = self.peek(now)?.task_id.clone();
+        self.remove(&task_id)
+    }
+
+    /// Task ids in dispatch order
+    pub fn order(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut entries: Vec<&QueuedTask> = self.entries.iter().collect();
+        entries.sort_by(|a, b| self.compare(a, b, now));
+        entries.into_iter().map(|entry| entry.task_id.clone()).collect()
+    }
+
+    /// Queue entry of a task, if it is queued
+    pub fn get(&self, task_id: &str) -> Option<&QueuedTask> {
+        self.entries.iter().find(|entry| entry.task_id == task_id)
+    }
+
+    pub fn contains(&self, task_id: &str) -> bool {
+        self.entries.iter().any(|entry| entry.task_id == task_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn compare(&self, a: &QueuedTask, b: &QueuedTask, now: DateTime<Utc>) -> std::cmp::Ordering {
+        b.effective_priority(now, &self.aging)
+            .cmp(&a.effective_priority(now, &self.aging))
+            .then(a.sequence.cmp(&b.sequence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_priority_then_submission_order() {
+        let now = Utc::now();
+        let mut queue = TaskQueue::new(AgingPolicy::default());
+        queue.push("batch-1", 1, now);
+        queue.push("interactive", 10, now);
+        queue.push("batch-2", 1, now);
+
+        assert_eq!(queue.order(now), vec!["interactive", "batch-1", "batch-2"]);
+        assert_eq!(queue.pop(now).unwrap().task_id, "interactive");
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_aging_prevents_starvation() {
+        let start = Utc::now();
+        let mut queue = TaskQueue::new(AgingPolicy { interval_ms: 100, step: 1 });
+        queue.push("old-batch", 1, start);
+
+        let t300 = start + Duration::milliseconds(300);
+        queue.push("interactive-1", 5, t300);
+        assert_eq!(queue.pop(t300).unwrap().task_id, "interactive-1");
+
+        // By 600ms the batch task has aged from 1 to 7, so it now beats
+        // freshly submitted interactive work at priority 5.
+        let t600 = start + Duration::milliseconds(600);
+        queue.push("interactive-2", 5, t600);
+        assert_eq!(queue.order(t600), vec!["old-batch", "interactive-2"]);
+    }
+}
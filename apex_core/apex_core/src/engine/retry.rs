@@ -0,0 +1,205 @@
+//! Retry Policies - ericadamsai watermark
+//! Exponential backoff with jitter for failed engine tasks
+
+use std::fmt;
+use std::sync::Arc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::TaskError;
+
+/// Decides whether a task error is worth retrying
+///
+/// A predicate is code and cannot be serialized. A policy read back from a
+/// snapshot only records that it had one, as a placeholder that retries
+/// nothing; see [`RetryPredicate::is_placeholder`].
+#[derive(Clone)]
+pub struct RetryPredicate {
+    predicate: Arc<dyn Fn(&TaskError) -> bool + Send + Sync>,
+    placeholder: bool,
+}
+
+impl RetryPredicate {
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&TaskError) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            predicate: Arc::new(predicate),
+            placeholder: false,
+        }
+    }
+
+    /// Stand-in for a predicate that was lost in serialization
+    fn placeholder() -> Self {
+        Self {
+            predicate: Arc::new(|_| false),
+            placeholder: true,
+        }
+    }
+
+    pub fn matches(&self, error: &TaskError) -> bool {
+        (self.predicate)(error)
+    }
+
+    /// Whether this stands in for a predicate that was lost when its policy
+    /// was serialized
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+}
+
+impl fmt::Debug for RetryPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.placeholder {
+            f.write_str("RetryPredicate(<lost>)")
+        } else {
+            f.write_str("RetryPredicate(..)")
+        }
+    }
+}
+
+/// Serializes a predicate as whether there is one
+mod predicate_flag {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::RetryPredicate;
+
+    pub fn serialize<S: Serializer>(predicate: &Option<RetryPredicate>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bool(predicate.is_some())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<RetryPredicate>, D::Error> {
+        Ok(bool::deserialize(deserializer)?.then(RetryPredicate::placeholder))
+    }
+}
+
+/// Retry policy for failed tasks
+///
+/// `max_attempts` counts the first run, so a value of 1 disables retries.
+/// The delay before attempt `n + 1` is `initial_backoff_ms * multiplier^(n - 1)`,
+/// capped at `max_backoff_ms` and reduced by up to `jitter` of itself at random.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of each delay that is randomized, between 0.0 and 1.0
+    pub jitter: f64,
+    /// Errors worth retrying; defaults to [`TaskError::is_retryable`]
+    ///
+    /// Serialized only as a flag, so a deserialized policy that had a
+    /// predicate holds a placeholder instead.
+    #[serde(default, with = "predicate_flag")]
+    pub retry_on: Option<RetryPredicate>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::exponential(1)
+        }
+    }
+
+    /// Exponential backoff starting at 100ms and doubling up to 10s
+    pub fn exponential(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 10_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_on: None,
+        }
+    }
+
+    /// Replace the predicate deciding which errors are retried
+    pub fn retry_on<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&TaskError) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Some(RetryPredicate::new(predicate));
+        self
+    }
+
+    /// Whether a task that just failed its `attempt`-th run should run again
+    pub fn should_retry(&self, attempt: u32, error: &TaskError) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        match &self.retry_on {
+            Some(predicate) => predicate.matches(error),
+            None => error.is_retryable(),
+        }
+    }
+
+    /// Delay after the `attempt`-th run before jitter is applied
+    pub fn backoff_ms(&self, attempt: u32) -> u64 {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let delay = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(exponent);
+        delay.min(self.max_backoff_ms as f64) as u64
+    }
+
+    /// Delay after the `attempt`-th run with jitter applied
+    pub fn delay_ms(&self, attempt: u32) -> u64 {
+        let base = self.backoff_ms(attempt);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 || base == 0 {
+            return base;
+        }
+        let reduction = rand::thread_rng().gen_range(0.0..=jitter);
+        (base as f64 * (1.0 - reduction)) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            max_backoff_ms: 500,
+            jitter: 0.0,
+            ..RetryPolicy::exponential(10)
+        };
+        assert_eq!(policy.backoff_ms(1), 100);
+        assert_eq!(policy.backoff_ms(2), 200);
+        assert_eq!(policy.backoff_ms(3), 400);
+        assert_eq!(policy.backoff_ms(4), 500);
+        assert_eq!(policy.delay_ms(2), 200);
+
+        let jittered = RetryPolicy::exponential(10);
+        let delay = jittered.delay_ms(2);
+        assert!((160..=200).contains(&delay));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::exponential(3);
+        let failure = TaskError::Failed("boom".to_string());
+        assert!(policy.should_retry(1, &failure));
+        assert!(policy.should_retry(2, &failure));
+        assert!(!policy.should_retry(3, &failure));
+        assert!(!policy.should_retry(1, &TaskError::Cancelled));
+
+        let policy = policy.retry_on(|e| matches!(e, TaskError::TimedOut(_)));
+        assert!(!policy.should_retry(1, &failure));
+        assert!(policy.should_retry(1, &TaskError::TimedOut(10)));
+        assert!(!RetryPolicy::none().should_retry(1, &failure));
+
+        let restored: RetryPolicy = serde_json::from_value(serde_json::to_value(&policy).unwrap()).unwrap();
+        assert!(restored.retry_on.unwrap().is_placeholder());
+        let restored: RetryPolicy = serde_json::from_value(serde_json::to_value(RetryPolicy::none()).unwrap()).unwrap();
+        assert!(restored.retry_on.is_none());
+    }
+}
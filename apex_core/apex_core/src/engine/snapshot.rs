@@ -0,0 +1,340 @@
+//! Engine Snapshots - ericadamsai watermark
+//! Persists engine state through the data store and recovers it on restart
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Interval};
+use tracing::{info, debug, warn};
+
+use super::{
+    ApeXEngine, EngineConfig, EngineEvent, EngineState, EventFilter, EventSubscription, TaskOutput,
+    TaskError, TaskSignals, TaskSpec, TaskStatus,
+};
+use crate::persist::DataStore;
+
+/// When engine state snapshots are written
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Write a snapshot at this interval
+    pub interval_ms: Option<u64>,
+    /// Write a snapshot after every engine event, coalescing bursts
+    pub on_change: bool,
+}
+
+/// What recovery does with tasks that were running at snapshot time
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum RecoveryPolicy {
+    /// Mark them `Interrupted` and leave them finished
+    #[default]
+    MarkInterrupted,
+    /// Queue them to run again from the start
+    Requeue,
+}
+
+impl ApeXEngine {
+    /// Write the current engine state to the data store
+    pub async fn snapshot(&self) -> Result<(), String> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| "Engine has no data store configured".to_string())?;
+        write_snapshot(store, &self.id, &self.get_state()).await
+    }
+
+    /// Start writing snapshots in the background according to
+    /// `EngineConfig.snapshot`
+    ///
+    /// Returns `None` if the engine has no data store or snapshots are
+    /// disabled. The background task only holds a weak reference to the
+    /// engine and stops once every engine handle is dropped; abort the
+    /// returned handle to stop it earlier.
+    pub fn start_snapshots(&self) -> Option<JoinHandle<()>> {
+        let store = self.store.clone()?;
+        let config = self.config.snapshot.clone();
+        if config.interval_ms.is_none() && !config.on_change {
+            return None;
+        }
+        info!("[ericadamsai] Starting engine snapshots: {} ({:?})", self.id, config);
+
+        let id = self.id.clone();
+        let state = Arc::downgrade(&self.state);
+        let mut events = self.subscribe(EventFilter::all());
+        let mut ticker = config
+            .interval_ms
+            .map(|ms| tokio::time::interval(Duration::from_millis(ms.max(1))));
+
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = next_change(&mut events), if config.on_change => {
+                        if event.is_none() {
+                            break;
+                        }
+                        // Coalesce a burst of events into one snapshot.
+                        while events.try_recv().is_some() {}
+                    }
+                    _ = next_tick(&mut ticker), if ticker.is_some() => {}
+                }
+                let snapshot = match state.upgrade() {
+                    Some(state) => state.lock().unwrap().clone(),
+                    None => break,
+                };
+                if let Err(e) = write_snapshot(&store, &id, &snapshot).await {
+                    warn!("[ericadamsai] Failed to write engine snapshot: {}", e);
+                }
+            }
+            debug!("[ericadamsai] Engine dropped, snapshots stopped: {}", id);
+        }))
+    }
+
+    /// Rebuild an engine from its last snapshot with the default configuration
+    pub async fn recover(id: String, store: Arc<DataStore>) -> Result<Self, String> {
+        Self::recover_with_config(id, EngineConfig::default(), store).await
+    }
+
+    /// Rebuild an engine from its last snapshot
+    ///
+    /// Tasks that were pending are queued again in their saved order, and
+    /// tasks that were running are handled according to `config.recovery`.
+    /// If any task is queued the engine starts paused, so executors can be
+    /// registered before calling [`ApeXEngine::resume`].
+    ///
+    /// Retry predicates are not saved with a snapshot. A task whose retry
+    /// policy had one is marked `Interrupted` rather than run again with
+    /// different retry behavior, and has to be resubmitted.
+    pub async fn recover_with_config(
+        id: String,
+        config: EngineConfig,
+        store: Arc<DataStore>,
+    ) -> Result<Self, String> {
+        let saved: EngineState = store.load(&Self::snapshot_key(&id)).await?;
+        let policy = config.recovery.clone();
+        let engine = Self::with_config(id, config).with_store(store);
+
+        let mut requeue = Vec::new();
+        let mut interrupted = 0;
+        {
+            let mut state = engine.state.lock().unwrap();
+            state.metrics = saved.metrics;
+            state.breakers = saved.breakers;
+            state.history = saved.history;
+            state.paused = saved.paused;
+            state.tasks = saved.tasks;
+
+            // Pending tasks keep their saved dispatch order; running tasks
+            // follow in submission order.
+            let now = Utc::now();
+            let positions: HashMap<String, usize> = saved
+                .queue
+                .order(now)
+                .into_iter()
+                .enumerate()
+                .map(|(position, task_id)| (task_id, position))
+                .collect();
+            let mut task_ids: Vec<String> = state
+                .tasks
+                .values()
+                .filter(|task| !task.status.is_terminal())
+                .map(|task| task.task_id.clone())
+                .collect();
+            task_ids.sort_by_key(|task_id| {
+                (positions.get(task_id).copied().unwrap_or(usize::MAX), state.tasks[task_id].created_at.clone())
+            });
+
+            for task_id in task_ids {
+                let was_running = state.tasks[&task_id].status == TaskStatus::Running;
+                let spec = saved.in_flight.get(&task_id).cloned();
+                let lost_predicate = spec
+                    .as_ref()
+                    .and_then(|spec| spec.retry.as_ref()?.retry_on.as_ref())
+                    .is_some_and(|predicate| predicate.is_placeholder());
+                match spec {
+                    Some(spec) if !lost_predicate && (!was_running || policy == RecoveryPolicy::Requeue) => {
+                        let enqueued_at = saved.queue.get(&task_id).map(|queued| queued.enqueued_at).unwrap_or(now);
+                        requeue.push((spec, enqueued_at));
+                    }
+                    _ => {
+                        if lost_predicate {
+                            warn!(
+                                "[ericadamsai] Task {} had a retry predicate that snapshots cannot keep; resubmit it",
+                                task_id
+                            );
+                        }
+                        let task = state.tasks.get_mut(&task_id).unwrap();
+                        task.status = TaskStatus::Interrupted;
+                        task.finished_at = Some(chrono::Local::now().to_rfc3339());
+                        state.history.push_back(task_id);
+                        state.metrics.interrupted_tasks += 1;
+                        interrupted += 1;
+                    }
+                }
+            }
+
+            if !requeue.is_empty() {
+                state.paused = true;
+            }
+            state.refresh_status();
+        }
+
+        for (task, enqueued_at) in requeue.iter().cloned() {
+            engine.requeue_task(task, enqueued_at);
+        }
+        if interrupted > 0 {
+            engine.telemetry.increment_counter("engine.task.interrupted", interrupted);
+        }
+        info!(
+            "[ericadamsai] Engine recovered: {} ({} re-queued, {} interrupted)",
+            engine.id,
+            requeue.len(),
+            interrupted
+        );
+        Ok(engine)
+    }
+
+    /// Put a known task back in the queue, keeping the time it was first
+    /// queued for aging, and run it in the background
+    fn requeue_task(&self, task: TaskSpec, enqueued_at: DateTime<Utc>) -> JoinHandle<Result<TaskOutput, TaskError>> {
+        let mut state = self.state.lock().unwrap();
+        let token = state.queue.push(&task.task_id, task.priority, enqueued_at);
+        state.in_flight.insert(task.task_id.clone(), task.clone());
+        if let Some(meta) = state.tasks.get_mut(&task.task_id) {
+            meta.status = TaskStatus::Pending;
+            meta.started_at = None;
+            meta.queue_wait_ms = None;
+        }
+        let signals = TaskSignals { token, ..TaskSignals::default() };
+        self.signals.lock().unwrap().insert(task.task_id.clone(), signals.clone());
+        drop(state);
+
+        self.emit(EngineEvent::TaskSubmitted {
+            task_id: task.task_id.clone(),
+            kind: task.kind.clone(),
+            priority: task.priority,
+        });
+        let engine = self.clone();
+        tokio::spawn(async move { engine.run_queued_task(task, signals).await })
+    }
+
+    fn snapshot_key(id: &str) -> String {
+        format!("engines/{}/state", id)
+    }
+}
+
+async fn write_snapshot(store: &DataStore, id: &str, state: &EngineState) -> Result<(), String> {
+    store.save(&ApeXEngine::snapshot_key(id), state).await?;
+    debug!("[ericadamsai] Engine snapshot written: {}", id);
+    Ok(())
+}
+
+async fn next_change(events: &mut EventSubscription) -> Option<EngineEvent> {
+    events.recv().await
+}
+
+async fn next_tick(ticker: &mut Option<Interval>) {
+    if let Some(ticker) = ticker {
+        ticker.tick().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::TaskExecutor;
+    use crate::persist::memory_store;
+    use async_trait::async_trait;
+
+    struct EchoExecutor;
+
+    #[async_trait]
+    impl TaskExecutor for EchoExecutor {
+        async fn execute(&self, task: &TaskSpec) -> Result<TaskOutput, TaskError> {
+            Ok(serde_json::json!(task.description))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover_requeues_pending_tasks() {
+        let store = memory_store();
+        let engine = ApeXEngine::new("snap-engine".to_string()).with_store(store.clone());
+        engine.pause();
+        let _pending = engine.spawn_task(TaskSpec::new("task-1".to_string(), "echo".to_string(), "hello".to_string()));
+        let mut urgent = TaskSpec::new("task-2".to_string(), "echo".to_string(), "urgent".to_string());
+        urgent.priority = 5;
+        let _urgent = engine.spawn_task(urgent);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        engine.snapshot().await.unwrap();
+        let enqueued_at = engine.get_state().queue.get("task-1").unwrap().enqueued_at;
+
+        let recovered = ApeXEngine::recover("snap-engine".to_string(), store).await.unwrap();
+        let state = recovered.get_state();
+        assert_eq!(state.status, crate::engine::ExecutionStatus::Paused);
+        assert_eq!(state.queue_order(), vec!["task-2", "task-1"]);
+        assert_eq!(state.queue.get("task-1").unwrap().enqueued_at, enqueued_at);
+        assert_eq!(state.metrics.total_tasks, 2);
+
+        let mut done = recovered.subscribe(EventFilter::task("task-1"));
+        recovered.register_executor("echo", Arc::new(EchoExecutor));
+        recovered.resume();
+        while let Some(event) = done.recv().await {
+            if matches!(event, EngineEvent::TaskCompleted { .. }) {
+                break;
+            }
+        }
+        let task = recovered.get_task("task-1").await.unwrap();
+        assert_eq!(task.output, Some(serde_json::json!("hello")));
+    }
+
+    #[tokio::test]
+    async fn test_recover_marks_running_tasks_interrupted() {
+        let store = memory_store();
+        let engine = ApeXEngine::new("snap-engine".to_string()).with_store(store.clone());
+        let mut spec = TaskSpec::new("task-1".to_string(), "echo".to_string(), "hello".to_string());
+        spec.priority = 3;
+        engine.register_task(&spec).unwrap();
+        engine.state.lock().unwrap().tasks.get_mut("task-1").unwrap().status = TaskStatus::Running;
+        let mut filtered = TaskSpec::new("task-2".to_string(), "echo".to_string(), "hello".to_string());
+        filtered.retry = Some(crate::engine::RetryPolicy::exponential(3).retry_on(|_| true));
+        engine.register_task(&filtered).unwrap();
+        engine.snapshot().await.unwrap();
+
+        let recovered = ApeXEngine::recover("snap-engine".to_string(), store).await.unwrap();
+        let state = recovered.get_state();
+        assert_eq!(state.tasks["task-1"].status, TaskStatus::Interrupted);
+        assert_eq!(state.tasks["task-2"].status, TaskStatus::Interrupted);
+        assert_eq!(state.metrics.interrupted_tasks, 2);
+        assert_eq!(state.queue_depth(), 0);
+        assert_eq!(state.status, crate::engine::ExecutionStatus::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_on_change() {
+        let store = memory_store();
+        let config = EngineConfig {
+            snapshot: SnapshotConfig { interval_ms: None, on_change: true },
+            ..EngineConfig::default()
+        };
+        let engine = ApeXEngine::with_config("snap-engine".to_string(), config).with_store(store.clone());
+        engine.register_executor("echo", Arc::new(EchoExecutor));
+        let snapshots = engine.start_snapshots().unwrap();
+
+        let task = TaskSpec::new("task-1".to_string(), "echo".to_string(), "hello".to_string());
+        engine.execute_task(task).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        snapshots.abort();
+
+        let saved: EngineState = store.load("engines/snap-engine/state").await.unwrap();
+        assert_eq!(saved.tasks["task-1"].status, TaskStatus::Completed);
+
+        let config = EngineConfig {
+            snapshot: SnapshotConfig { interval_ms: Some(5), on_change: false },
+            ..EngineConfig::default()
+        };
+        let engine = ApeXEngine::with_config("snap-engine".to_string(), config).with_store(store.clone());
+        let snapshots = engine.start_snapshots().unwrap();
+        drop(engine);
+        tokio::time::timeout(Duration::from_secs(1), snapshots).await.unwrap().unwrap();
+    }
+}
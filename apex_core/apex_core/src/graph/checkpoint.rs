@@ -0,0 +1,223 @@
+//! Graph Checkpoints - ericadamsai watermark
+//! Persists completed node outputs so failed graph runs can be resumed
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{ExecutionGraph, GraphError, GraphExecutor, NodeInputs, NodeOutput, NodeResult};
+use crate::persist::DataStore;
+
+/// Outputs of the nodes that completed in a graph run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphCheckpoint {
+    pub run_id: String,
+    pub graph_id: String,
+    pub outputs: HashMap<String, NodeOutput>,
+}
+
+impl GraphCheckpoint {
+    /// Create an empty checkpoint for a run of a graph
+    pub fn new(run_id: &str, graph_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            graph_id: graph_id.to_string(),
+            outputs: HashMap::new(),
+        }
+    }
+
+    /// Load the checkpoint of a run from a data store
+    pub async fn load(store: &DataStore, run_id: &str) -> Result<Self, GraphError> {
+        store.load(&Self::key(run_id)).await.map_err(GraphError::Execution)
+    }
+
+    /// Write the checkpoint to a data store
+    pub async fn save(&self, store: &DataStore) -> Result<(), GraphError> {
+        store.save(&Self::key(&self.run_id), self).await.map_err(GraphError::Execution)
+    }
+
+    fn key(run_id: &str) -> String {
+        format!("graph-runs/{}/checkpoint", run_id)
+    }
+}
+
+impl GraphExecutor {
+    /// Run every node of the graph, checkpointing the output of each node
+    /// that completes under `run_id`
+    ///
+    /// Any earlier checkpoint of the same run id is replaced.
+    pub async fn run_checkpointed(
+        &self,
+        graph: &mut ExecutionGraph,
+        run_id: &str,
+    ) -> Result<HashMap<String, NodeResult>, GraphError> {
+        let checkpoint = GraphCheckpoint::new(run_id, &graph.id);
+        checkpoint.save(self.checkpoint_store()?).await?;
+        info!("[ericadamsai] Starting checkpointed run {} of graph {}", run_id, graph.id);
+        self.run_seeded(graph, NodeInputs::new(), Some(checkpoint)).await
+    }
+
+    /// Continue a checkpointed run
+    ///
+    /// Nodes that completed are not run again; their checkpointed outputs
+    /// are used instead, and their results have no start or finish time.
+    /// Nodes that failed, were skipped or never started run as usual.
+    pub async fn resume(
+        &self,
+        graph: &mut ExecutionGraph,
+        run_id: &str,
+    ) -> Result<HashMap<String, NodeResult>, GraphError> {
+        self.resume_run(graph, run_id, None).await
+    }
+
+    /// Continue a checkpointed run, running `node_id` and every node
+    /// downstream of it again even if they completed
+    pub async fn rerun_from(
+        &self,
+        graph: &mut ExecutionGraph,
+        run_id: &str,
+        node_id: &str,
+    ) -> Result<HashMap<String, NodeResult>, GraphError> {
+        self.resume_run(graph, run_id, Some(node_id)).await
+    }
+
+    async fn resume_run(
+        &self,
+        graph: &mut ExecutionGraph,
+        run_id: &str,
+        rerun: Option<&str>,
+    ) -> Result<HashMap<String, NodeResult>, GraphError> {
+        let store = self.checkpoint_store()?;
+        let mut checkpoint = GraphCheckpoint::load(store, run_id).await?;
+        if checkpoint.graph_id != graph.id {
+            return Err(GraphError::Execution(format!(
+                "run {} is a run of graph {}, not {}",
+                run_id, checkpoint.graph_id, graph.id
+            )));
+        }
+        if let Some(node_id) = rerun {
+            let invalidated = graph.downstream(node_id)?;
+            checkpoint.outputs.retain(|id, _| !invalidated.contains(id));
+            checkpoint.save(store).await?;
+        }
+        info!(
+            "[ericadamsai] Resuming run {} of graph {} ({} nodes checkpointed)",
+            run_id,
+            graph.id,
+            checkpoint.outputs.len()
+        );
+        self.run_seeded(graph, NodeInputs::new(), Some(checkpoint)).await
+    }
+
+    /// Add a completed node's output to the checkpoint and save it
+    ///
+    /// A failed save is logged rather than failing the run, since it only
+    /// means the node runs again on resume.
+    pub(super) async fn save_checkpoint(&self, checkpoint: Option<&mut GraphCheckpoint>, result: &NodeResult) {
+        let (Some(checkpoint), Some(store)) = (checkpoint, &self.store) else {
+            return;
+        };
+        let Some(output) = result.output.as_ref().filter(|_| result.is_completed()) else {
+            return;
+        };
+        checkpoint.outputs.insert(result.node_id.clone(), output.clone());
+        if let Err(e) = checkpoint.save(store).await {
+            warn!("[ericadamsai] Failed to checkpoint graph node {}: {}", result.node_id, e);
+        }
+    }
+
+    fn checkpoint_store(&self) -> Result<&Arc<DataStore>, GraphError> {
+        self.store
+            .as_ref()
+            .ok_or_else(|| GraphError::Execution("graph executor has no data store configured".to_string()))
+    }
+}
+
+impl ExecutionGraph {
+    /// Ids of a node and of every node reachable from it
+    pub fn downstream(&self, node_id: &str) -> Result<HashSet<String>, GraphError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(GraphError::NodeNotFound { node_id: node_id.to_string() });
+        }
+        let mut reached = HashSet::from([node_id.to_string()]);
+        let mut pending = vec![node_id.to_string()];
+        while let Some(current) = pending.pop() {
+            for edge in self.edges.iter().filter(|edge| edge.from == current) {
+                if reached.insert(edge.to.clone()) {
+                    pending.push(edge.to.clone());
+                }
+            }
+        }
+        Ok(reached)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{plain_edge, task_node, GraphNode, NodeError, NodeExecutor, NodeStatus};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    /// Counts runs per node and fails `b` while `broken` is set
+    #[derive(Default)]
+    struct FlakyExecutor {
+        broken: AtomicBool,
+        runs: Mutex<HashMap<String, usize>>,
+    }
+
+    #[async_trait]
+    impl NodeExecutor for FlakyExecutor {
+        async fn execute(&self, node: &GraphNode, inputs: &NodeInputs) -> Result<NodeOutput, NodeError> {
+            *self.runs.lock().unwrap().entry(node.id.clone()).or_default() += 1;
+            if node.id == "b" && self.broken.load(Ordering::SeqCst) {
+                return Err(NodeError::Failed("broken".to_string()));
+            }
+            let total: i64 = inputs.values().filter_map(|v| v.as_i64()).sum();
+            Ok(serde_json::json!(total + 1))
+        }
+    }
+
+    fn chain() -> ExecutionGraph {
+        let mut graph = ExecutionGraph::new("chain".to_string());
+        for id in ["a", "b", "c"] {
+            graph.add_node(task_node(id)).unwrap();
+        }
+        for (from, to) in [("a", "b"), ("b", "c")] {
+            graph.add_edge(plain_edge(from, to)).unwrap();
+        }
+        graph
+    }
+
+    #[tokio::test]
+    async fn test_resume_and_rerun_from_checkpoint() {
+        let store = crate::persist::memory_store();
+        let flaky = Arc::new(FlakyExecutor::default());
+        flaky.broken.store(true, Ordering::SeqCst);
+        let executor = GraphExecutor::new(flaky.clone()).with_store(store.clone());
+        let mut graph = chain();
+
+        let results = executor.run_checkpointed(&mut graph, "run-1").await.unwrap();
+        assert!(matches!(results["b"].status, NodeStatus::Failed(_)));
+        assert!(matches!(results["c"].status, NodeStatus::Skipped(_)));
+
+        flaky.broken.store(false, Ordering::SeqCst);
+        let results = executor.resume(&mut graph, "run-1").await.unwrap();
+        assert!(results.values().all(|result| result.is_completed()));
+        assert_eq!(results["a"].started_at, None);
+        assert_eq!(results["c"].output, Some(serde_json::json!(3)));
+        assert_eq!(graph.execution_order, vec!["b", "c"]);
+
+        executor.rerun_from(&mut graph, "run-1", "b").await.unwrap();
+        let runs = flaky.runs.lock().unwrap().clone();
+        assert_eq!(runs, HashMap::from([("a".to_string(), 1), ("b".to_string(), 3), ("c".to_string(), 2)]));
+
+        assert!(matches!(
+            executor.rerun_from(&mut graph, "run-1", "missing").await,
+            Err(GraphError::NodeNotFound { .. })
+        ));
+        assert!(GraphExecutor::new(flaky).resume(&mut graph, "run-1").await.is_err());
+    }
+}
@@ -0,0 +1,583 @@
+//! Edge Conditions - ericadamsai watermark
+//! Small expression language deciding at runtime whether a graph edge is taken
+//!
+//! Conditions are boolean expressions over the output and metadata of the
+//! edge's source node:
+//!
+//! ```text
+//! score > 0.8 && status == "ok"
+//! !(output.retries >= 3) || metadata.force == true
+//! output.labels[0] != null
+//! ```
+//!
+//! A bare name such as `score` looks up that field of the source node's
+//! output, then of its metadata; `output` and `metadata` refer to the whole
+//! values. Supported operators are `||`, `&&`, `!`, `==`, `!=`, `<`, `<=`,
+//! `>` and `>=`, with parentheses for grouping. Literals are numbers,
+//! double- or single-quoted strings, `true`, `false` and `null`. Missing
+//! fields evaluate to `null`, and the expression is true when its value is
+//! truthy: not `null`, `false`, `0`, `""` or an empty array or object.
+//!
+//! Conditions in graph templates may use `{{parameter}}` placeholders in
+//! place of a literal; see [`Condition::fill`].
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use serde_json::Value;
+
+/// Deepest nesting of `!` and parentheses a condition may use, so that
+/// conditions from untrusted documents cannot exhaust the stack
+const MAX_DEPTH: usize = 64;
+
+/// Error produced while parsing or evaluating a condition
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum ConditionError {
+    #[error("invalid condition at position {position}: {message}")]
+    Parse { position: usize, message: String },
+    #[error("condition evaluation failed: {0}")]
+    Eval(String),
+}
+
+/// Values a condition is evaluated against
+#[derive(Clone, Copy, Debug)]
+pub struct ConditionContext<'a> {
+    /// Output of the edge's source node
+    pub output: Option<&'a Value>,
+    /// Metadata of the edge's source node
+    pub metadata: &'a Value,
+}
+
+/// Parsed edge condition
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parse a condition expression
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, index: 0, len: source.len(), depth: 0 };
+        let expr = parser.parse_or()?;
+        if let Some((position, token)) = parser.tokens.get(parser.index) {
+            return Err(parse_error(*position, format!("unexpected {}", token)));
+        }
+        Ok(Self { source: source.to_string(), expr })
+    }
+
+    /// Parse a template condition after replacing each `{{parameter}}`
+    /// placeholder with that parameter's value as a literal
+    ///
+    /// Strings are quoted, so a value can never change the structure of the
+    /// expression, and placeholders inside string literals are left alone.
+    /// Arrays and objects have no literal syntax and are rejected.
+    pub fn fill(source: &str, values: &HashMap<String, Value>) -> Result<Self, ConditionError> {
+        let mut filled = String::with_capacity(source.len());
+        let mut copied = 0;
+        for (position, token) in tokenize(source)? {
+            let Token::Placeholder(name) = token else {
+                continue;
+            };
+            let value = values
+                .get(&name)
+                .ok_or_else(|| parse_error(position, format!("unknown template parameter `{}`", name)))?;
+            let literal = literal(value).ok_or_else(|| {
+                parse_error(position, format!("template parameter `{}` is not a string, number, boolean or null", name))
+            })?;
+            filled.push_str(&source[copied..position]);
+            filled.push_str(&literal);
+            copied = position + source[position..].find("}}").unwrap() + 2;
+        }
+        filled.push_str(&source[copied..]);
+        Self::parse(&filled)
+    }
+
+    /// Evaluate the condition to a boolean
+    pub fn evaluate(&self, context: &ConditionContext) -> Result<bool, ConditionError> {
+        Ok(truthy(&self.expr.evaluate(context)?))
+    }
+
+    /// Original expression text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Path(Vec<Segment>),
+    Not(Box<Expr>),
+    /// Operands of a chain of `&&`, so long chains do not nest
+    And(Vec<Expr>),
+    /// Operands of a chain of `||`
+    Or(Vec<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Expr {
+    fn evaluate(&self, context: &ConditionContext) -> Result<Value, ConditionError> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Path(segments) => Ok(resolve(segments, context)),
+            Expr::Not(inner) => Ok(Value::Bool(!truthy(&inner.evaluate(context)?))),
+            Expr::And(operands) => {
+                for operand in operands {
+                    if !truthy(&operand.evaluate(context)?) {
+                        return Ok(Value::Bool(false));
+                    }
+                }
+                Ok(Value::Bool(true))
+            }
+            Expr::Or(operands) => {
+                for operand in operands {
+                    if truthy(&operand.evaluate(context)?) {
+                        return Ok(Value::Bool(true));
+                    }
+                }
+                Ok(Value::Bool(false))
+            }
+            Expr::Compare(op, left, right) => {
+                let left = left.evaluate(context)?;
+                let right = right.evaluate(context)?;
+                compare(*op, &left, &right).map(Value::Bool)
+            }
+        }
+    }
+}
+
+fn resolve(segments: &[Segment], context: &ConditionContext) -> Value {
+    let (root, rest) = match &segments[0] {
+        Segment::Field(name) if name == "output" => (context.output, &segments[1..]),
+        Segment::Field(name) if name == "metadata" => (Some(context.metadata), &segments[1..]),
+        Segment::Field(name) => {
+            let in_output = context.output.and_then(|output| output.get(name));
+            (in_output.or_else(|| context.metadata.get(name)), &segments[1..])
+        }
+        Segment::Index(_) => (None, segments),
+    };
+    let mut current = root;
+    for segment in rest {
+        current = current.and_then(|value| match segment {
+            Segment::Field(name) => value.get(name),
+            Segment::Index(index) => value.get(index),
+        });
+    }
+    current.cloned().unwrap_or(Value::Null)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn compare(op: CompareOp, left: &Value, right: &Value) -> Result<bool, ConditionError> {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match (op, ordering) {
+        (CompareOp::Eq, Some(ordering)) => Ok(ordering == Ordering::Equal),
+        (CompareOp::Ne, Some(ordering)) => Ok(ordering != Ordering::Equal),
+        (CompareOp::Eq, None) => Ok(left == right),
+        (CompareOp::Ne, None) => Ok(left != right),
+        (CompareOp::Lt, Some(ordering)) => Ok(ordering == Ordering::Less),
+        (CompareOp::Le, Some(ordering)) => Ok(ordering != Ordering::Greater),
+        (CompareOp::Gt, Some(ordering)) => Ok(ordering == Ordering::Greater),
+        (CompareOp::Ge, Some(ordering)) => Ok(ordering != Ordering::Less),
+        (_, None) => Err(ConditionError::Eval(format!("cannot order {} and {}", left, right))),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    Dot,
+    Minus,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    /// `{{name}}`, only valid before [`Condition::fill`]
+    Placeholder(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Str(s) => write!(f, "string {:?}", s),
+            Token::Ident(name) => write!(f, "`{}`", name),
+            Token::Op(op) => write!(f, "`{}`", op),
+            Token::Dot => f.write_str("`.`"),
+            Token::Minus => f.write_str("`-`"),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+            Token::LBracket => f.write_str("`[`"),
+            Token::RBracket => f.write_str("`]`"),
+            Token::Placeholder(name) => write!(f, "template parameter `{{{{{}}}}}`", name),
+        }
+    }
+}
+
+fn parse_error(position: usize, message: String) -> ConditionError {
+    ConditionError::Parse { position, message }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ConditionError> {
+    const OPERATORS: [&str; 9] = ["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!"];
+
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let rest = &source[position..];
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push((position, Token::Op(op)));
+            continue;
+        }
+        let simple = match c {
+            '.' => Some(Token::Dot),
+            '-' => Some(Token::Minus),
+            '(' => Some(Token::LParen),
+            ')' => Some(Token::RParen),
+            '[' => Some(Token::LBracket),
+            ']' => Some(Token::RBracket),
+            _ => None,
+        };
+        if let Some(token) = simple {
+            chars.next();
+            tokens.push((position, token));
+        } else if let Some(rest) = rest.strip_prefix("{{") {
+            let end = rest
+                .find("}}")
+                .ok_or_else(|| parse_error(position, "unterminated template parameter".to_string()))?;
+            while chars.peek().is_some_and(|(i, _)| *i < position + end + 4) {
+                chars.next();
+            }
+            tokens.push((position, Token::Placeholder(rest[..end].trim().to_string())));
+        } else if c.is_ascii_digit() {
+            let mut end = position;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = source[position..end]
+                .parse()
+                .map_err(|_| parse_error(position, format!("invalid number `{}`", &source[position..end])))?;
+            tokens.push((position, Token::Number(number)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            tokens.push((position, Token::Ident(name)));
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, escaped)) => value.push(escaped),
+                        None => return Err(parse_error(position, "unterminated string".to_string())),
+                    },
+                    Some((_, end)) if end == c => break,
+                    Some((_, other)) => value.push(other),
+                    None => return Err(parse_error(position, "unterminated string".to_string())),
+                }
+            }
+            tokens.push((position, Token::Str(value)));
+        } else {
+            return Err(parse_error(position, format!("unexpected character `{}`", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser; precedence from loosest to tightest is
+/// `||`, `&&`, comparison, `!`
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    len: usize,
+    /// Current nesting of `!` and parentheses
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.len, |(position, _)| *position)
+    }
+
+    fn next(&mut self) -> Result<Token, ConditionError> {
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        token.ok_or_else(|| parse_error(self.len, "unexpected end of expression".to_string()))
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ConditionError> {
+        let position = self.position();
+        let token = self.next()?;
+        if token != expected {
+            return Err(parse_error(position, format!("expected {}, found {}", expected, token)));
+        }
+        Ok(())
+    }
+
+    /// Parse with `parse` one nesting level below the just consumed `!` or
+    /// `(`, failing past `MAX_DEPTH`
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, ConditionError>) -> Result<Expr, ConditionError> {
+        if self.depth == MAX_DEPTH {
+            let position = self.tokens[self.index - 1].0;
+            return Err(parse_error(position, format!("nested deeper than {} levels", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ConditionError> {
+        let mut operands = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Op("||")) {
+            self.index += 1;
+            operands.push(self.parse_and()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { Expr::Or(operands) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ConditionError> {
+        let mut operands = vec![self.parse_comparison()?];
+        while self.peek() == Some(&Token::Op("&&")) {
+            self.index += 1;
+            operands.push(self.parse_comparison()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { Expr::And(operands) })
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ConditionError> {
+        let left = self.parse_unary()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            _ => return Ok(left),
+        };
+        self.index += 1;
+        let right = self.parse_unary()?;
+        Ok(Expr::Compare(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ConditionError> {
+        if self.peek() == Some(&Token::Op("!")) {
+            self.index += 1;
+            return Ok(Expr::Not(Box::new(self.nested(Self::parse_unary)?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ConditionError> {
+        let position = self.position();
+        match self.next()? {
+            Token::Number(n) => Ok(Expr::Literal(number(n))),
+            Token::Minus => match self.next()? {
+                Token::Number(n) => Ok(Expr::Literal(number(-n))),
+                token => Err(parse_error(position, format!("expected number after `-`, found {}", token))),
+            },
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::LParen => {
+                let expr = self.nested(Self::parse_or)?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => self.parse_path(name),
+            },
+            token => Err(parse_error(position, format!("unexpected {}", token))),
+        }
+    }
+
+    fn parse_path(&mut self, root: String) -> Result<Expr, ConditionError> {
+        let mut segments = vec![Segment::Field(root)];
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.index += 1;
+                    let position = self.position();
+                    match self.next()? {
+                        Token::Ident(name) => segments.push(Segment::Field(name)),
+                        token => return Err(parse_error(position, format!("expected field name, found {}", token))),
+                    }
+                }
+                Some(Token::LBracket) => {
+                    self.index += 1;
+                    let position = self.position();
+                    match self.next()? {
+                        Token::Number(n) if n.fract() == 0.0 && n >= 0.0 => {
+                            segments.push(Segment::Index(n as usize))
+                        }
+                        Token::Str(name) => segments.push(Segment::Field(name)),
+                        token => return Err(parse_error(position, format!("expected index, found {}", token))),
+                    }
+                    self.expect(Token::RBracket)?;
+                }
+                _ => return Ok(Expr::Path(segments)),
+            }
+        }
+    }
+}
+
+fn number(n: f64) -> Value {
+    serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+/// Condition source text for a value, if it has a literal syntax
+fn literal(value: &Value) -> Option<String> {
+    match value {
+        Value::Null | Value::Bool(_) => Some(value.to_string()),
+        Value::Number(n) => n.as_f64().map(|n| n.to_string()),
+        Value::String(s) => {
+            let escaped = s
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\t', "\\t");
+            Some(format!("\"{}\"", escaped))
+        }
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(source: &str, output: &Value, metadata: &Value) -> Result<bool, ConditionError> {
+        let context = ConditionContext { output: Some(output), metadata };
+        Condition::parse(source)?.evaluate(&context)
+    }
+
+    #[test]
+    fn test_evaluate_conditions() {
+        let output = json!({"score": 0.9, "status": "ok", "labels": ["cat"], "count": 3});
+        let metadata = json!({"force": false, "status": "ignored"});
+
+        assert!(eval("score > 0.8 && status == \"ok\"", &output, &metadata).unwrap());
+        assert!(!eval("score > 0.95 || metadata.force", &output, &metadata).unwrap());
+        assert!(eval("!(count >= 4) && output.labels[0] == 'cat'", &output, &metadata).unwrap());
+        assert!(eval("count == 3.0 && missing == null && force == false", &output, &metadata).unwrap());
+        assert!(eval("metadata[\"status\"] != status", &output, &metadata).unwrap());
+        assert!(eval("count > -1", &output, &metadata).unwrap());
+        assert!(!eval("labels[5]", &output, &metadata).unwrap());
+        assert!(matches!(
+            eval("status > 1", &output, &metadata),
+            Err(ConditionError::Eval(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Condition::parse("score > 0.8 &&"),
+            Err(parse_error(14, "unexpected end of expression".to_string()))
+        );
+        assert_eq!(
+            Condition::parse("score = 1"),
+            Err(parse_error(6, "unexpected character `=`".to_string()))
+        );
+        assert_eq!(
+            Condition::parse("(score > 1"),
+            Err(parse_error(10, "unexpected end of expression".to_string()))
+        );
+        assert_eq!(
+            Condition::parse("score 1"),
+            Err(parse_error(6, "unexpected number 1".to_string()))
+        );
+        assert!("status == \"ok\"".parse::<Condition>().is_ok());
+    }
+
+    #[test]
+    fn test_nesting_is_bounded() {
+        let nested = format!("{}true{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(Condition::parse(&nested).is_ok());
+        for source in ["!".repeat(100_000) + "true", "(".repeat(100_000)] {
+            assert_eq!(
+                Condition::parse(&source),
+                Err(parse_error(MAX_DEPTH, format!("nested deeper than {} levels", MAX_DEPTH)))
+            );
+        }
+
+        let chain = vec!["count > 1"; 100_000].join(" || ");
+        let context = ConditionContext { output: Some(&json!({"count": 0})), metadata: &Value::Null };
+        assert!(!Condition::parse(&chain).unwrap().evaluate(&context).unwrap());
+    }
+}
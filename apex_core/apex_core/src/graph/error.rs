@@ -0,0 +1,83 @@
+//! Graph Errors - ericadamsai watermark
+//! Structured errors for building, validating and running execution graphs
+
+use super::ConditionError;
+
+/// Error produced while building, validating or running a graph
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum GraphError {
+    #[error("node {node_id} already exists")]
+    DuplicateNode { node_id: String },
+    #[error("node {node_id} not found")]
+    NodeNotFound { node_id: String },
+    #[error("edge {from} -> {to} not found")]
+    EdgeNotFound { from: String, to: String },
+    #[error("edge {from} -> {to} references non-existent node {missing}")]
+    DanglingEdge { from: String, to: String, missing: String },
+    #[error("edge {node_id} -> {node_id} is a self-loop")]
+    SelfLoop { node_id: String },
+    #[error("edge {from} -> {to} is declared more than once")]
+    DuplicateEdge { from: String, to: String },
+    /// An edge into the node whose source is not in its `dependencies`
+    #[error("edge {dependency} -> {node_id} is not listed in the dependencies of {node_id}")]
+    UndeclaredDependency { node_id: String, dependency: String },
+    /// A node in `dependencies` without a matching edge
+    #[error("node {node_id} depends on {dependency} but there is no edge {dependency} -> {node_id}")]
+    MissingDependencyEdge { node_id: String, dependency: String },
+    /// An input a node expects that none of the nodes with an edge into it
+    /// list among their outputs
+    #[error("node {node_id} expects input {input} but no upstream node outputs it")]
+    MissingInput { node_id: String, input: String },
+    #[error("edge {from} -> {to} has an {error}")]
+    InvalidCondition { from: String, to: String, error: ConditionError },
+    #[error("node {node_id} has invalid configuration: {message}")]
+    InvalidNodeConfig { node_id: String, message: String },
+    #[error("subgraph of node {node_id} is invalid: {error}")]
+    InvalidSubgraph { node_id: String, error: Box<GraphError> },
+    #[error("template {template} requires parameter {parameter}")]
+    MissingParameter { template: String, parameter: String },
+    #[error("template {template} has no parameter {parameter}")]
+    UnknownParameter { template: String, parameter: String },
+    /// The nodes of one cycle, in edge order
+    #[error("cycle detected: {}", cycle_path(nodes))]
+    Cycle { nodes: Vec<String> },
+    #[error("patch {index} failed: {error}")]
+    Patch { index: usize, error: Box<GraphError> },
+    #[error("invalid {format} graph document: {message}")]
+    InvalidDocument { format: String, message: String },
+    #[error("graph execution failed: {0}")]
+    Execution(String),
+}
+
+impl GraphError {
+    /// Ids of the nodes the error is about
+    pub fn node_ids(&self) -> Vec<&str> {
+        match self {
+            GraphError::DuplicateNode { node_id }
+            | GraphError::NodeNotFound { node_id }
+            | GraphError::SelfLoop { node_id }
+            | GraphError::InvalidNodeConfig { node_id, .. }
+            | GraphError::InvalidSubgraph { node_id, .. }
+            | GraphError::MissingInput { node_id, .. } => vec![node_id],
+            GraphError::DanglingEdge { from, to, .. }
+            | GraphError::EdgeNotFound { from, to }
+            | GraphError::DuplicateEdge { from, to }
+            | GraphError::InvalidCondition { from, to, .. } => vec![from, to],
+            GraphError::UndeclaredDependency { node_id, dependency }
+            | GraphError::MissingDependencyEdge { node_id, dependency } => vec![node_id, dependency],
+            GraphError::Cycle { nodes } => nodes.iter().map(String::as_str).collect(),
+            GraphError::Patch { error, .. } => error.node_ids(),
+            GraphError::MissingParameter { .. }
+            | GraphError::UnknownParameter { .. }
+            | GraphError::InvalidDocument { .. }
+            | GraphError::Execution(_) => vec![],
+        }
+    }
+}
+
+/// Render a cycle as `a -> b -> c -> a`
+fn cycle_path(nodes: &[String]) -> String {
+    let mut path = nodes.to_vec();
+    path.extend(nodes.first().cloned());
+    path.join(" -> ")
+}
@@ -0,0 +1,882 @@
+//! Graph Executor - ericadamsai watermark
+//! Runs ExecutionGraph nodes concurrently as soon as their dependencies finish
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{info, debug, warn};
+
+use super::checkpoint::GraphCheckpoint;
+use super::condition::{Condition, ConditionContext};
+use super::nodes::{AggregateStrategy, LoopConfig};
+use super::subgraph::SubgraphConfig;
+use super::{ExecutionGraph, GraphError, GraphNode, NodeType};
+use crate::persist::DataStore;
+
+/// Output produced by a node executor
+pub type NodeOutput = serde_json::Value;
+
+/// Outputs of a node's upstream nodes along taken edges, keyed by node id
+pub type NodeInputs = HashMap<String, NodeOutput>;
+
+/// Future of a graph run, boxed so loop bodies can run recursively
+type BoxedRun<'a> = Pin<Box<dyn Future<Output = Result<HashMap<String, NodeResult>, GraphError>> + Send + 'a>>;
+
+/// Error produced while executing a graph node
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, thiserror::Error)]
+pub enum NodeError {
+    #[error("node execution failed: {0}")]
+    Failed(String),
+    #[error("node executor panicked: {0}")]
+    Panicked(String),
+}
+
+/// Pluggable executor for graph nodes
+#[async_trait]
+pub trait NodeExecutor: Send + Sync {
+    /// Execute a single node given the outputs of its upstream nodes
+    async fn execute(&self, node: &GraphNode, inputs: &NodeInputs) -> Result<NodeOutput, NodeError>;
+}
+
+/// Graph executor configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphExecutorConfig {
+    /// Maximum number of nodes executing at the same time in a run,
+    /// counting the nodes of nested subgraph and loop runs
+    pub max_concurrency: usize,
+    /// Stop starting new nodes after the first failure
+    pub fail_fast: bool,
+}
+
+impl Default for GraphExecutorConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 8,
+            fail_fast: false,
+        }
+    }
+}
+
+/// Outcome of a node in a graph run
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum NodeStatus {
+    Completed,
+    Failed(String),
+    /// The node never ran, for the given reason
+    Skipped(String),
+}
+
+/// Result of a single node in a graph run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeResult {
+    pub node_id: String,
+    pub status: NodeStatus,
+    pub output: Option<NodeOutput>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl NodeResult {
+    fn finished(node_id: String, result: Result<NodeOutput, NodeError>, started_at: String, duration_ms: u64) -> Self {
+        let (status, output) = match result {
+            Ok(output) => (NodeStatus::Completed, Some(output)),
+            Err(e) => (NodeStatus::Failed(e.to_string()), None),
+        };
+        Self {
+            node_id,
+            status,
+            output,
+            started_at: Some(started_at),
+            finished_at: Some(chrono::Local::now().to_rfc3339()),
+            duration_ms,
+        }
+    }
+
+    /// Result of a node whose output was restored from a checkpoint; it
+    /// has no start or finish time since it did not run
+    fn restored(node_id: String, output: NodeOutput) -> Self {
+        Self {
+            node_id,
+            status: NodeStatus::Completed,
+            output: Some(output),
+            started_at: None,
+            finished_at: None,
+            duration_ms: 0,
+        }
+    }
+
+    fn skipped(node_id: &str, reason: String) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            status: NodeStatus::Skipped(reason),
+            output: None,
+            started_at: None,
+            finished_at: None,
+            duration_ms: 0,
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.status == NodeStatus::Completed
+    }
+}
+
+/// Runs an `ExecutionGraph` with a node executor
+///
+/// A node starts once every node with an edge into it has finished. An edge
+/// is taken when its source completed and its condition, if any, holds; the
+/// node receives the outputs of the sources of its taken edges as inputs.
+///
+/// A node is skipped if an upstream node failed, or if none of its incoming
+/// edges were taken, which in turn skips the rest of an untaken branch.
+/// Nodes without incoming edges always run. A condition that cannot be
+/// evaluated counts as not taken.
+#[derive(Clone)]
+pub struct GraphExecutor {
+    pub config: GraphExecutorConfig,
+    executor: Arc<dyn NodeExecutor>,
+    pub(super) store: Option<Arc<DataStore>>,
+}
+
+impl GraphExecutor {
+    /// Create a new graph executor with the default configuration
+    pub fn new(executor: Arc<dyn NodeExecutor>) -> Self {
+        Self::with_config(executor, GraphExecutorConfig::default())
+    }
+
+    /// Create a new graph executor with the given configuration
+    pub fn with_config(executor: Arc<dyn NodeExecutor>, config: GraphExecutorConfig) -> Self {
+        Self {
+            config,
+            executor,
+            store: None,
+        }
+    }
+
+    /// Checkpoint graph runs to a data store; see [`GraphExecutor::run_checkpointed`]
+    pub fn with_store(mut self, store: Arc<DataStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Run every node of the graph and return the result of each node
+    ///
+    /// The order in which nodes were started is recorded in
+    /// `graph.execution_order`.
+    pub async fn run(&self, graph: &mut ExecutionGraph) -> Result<HashMap<String, NodeResult>, GraphError> {
+        self.run_seeded(graph, NodeInputs::new(), None).await
+    }
+
+    /// Run a graph whose nodes without incoming edges receive `seed` as inputs
+    ///
+    /// With a checkpoint, nodes it holds an output for are not run again,
+    /// and the output of every node that completes is added to it and saved.
+    pub(super) fn run_seeded<'a>(
+        &'a self,
+        graph: &'a mut ExecutionGraph,
+        seed: NodeInputs,
+        checkpoint: Option<GraphCheckpoint>,
+    ) -> BoxedRun<'a> {
+        let permits = Arc::new(Semaphore::new(self.config.max_concurrency.max(1)));
+        self.run_with_permits(graph, seed, checkpoint, permits)
+    }
+
+    /// Run a graph whose node executions each hold one of `permits`, which
+    /// nested runs share with the run that started them
+    fn run_with_permits<'a>(
+        &'a self,
+        graph: &'a mut ExecutionGraph,
+        seed: NodeInputs,
+        mut checkpoint: Option<GraphCheckpoint>,
+        permits: Arc<Semaphore>,
+    ) -> BoxedRun<'a> {
+        Box::pin(async move {
+            graph.validate()?;
+            graph.topological_sort()?;
+            info!("[ericadamsai] Running graph {} ({} nodes)", graph.id, graph.nodes.len());
+
+            let mut run = GraphRun::new(graph, seed)?;
+            let mut running = JoinSet::new();
+            // Node id, start time and start instant of each running node task
+            let mut started: HashMap<tokio::task::Id, (String, String, Instant)> = HashMap::new();
+            let mut reserved: Option<OwnedSemaphorePermit> = None;
+
+            loop {
+                let mut blocked = false;
+                // Position in `run.ready` of the next node to consider; nodes
+                // before it are waiting for a permit.
+                let mut index = 0;
+                while !run.halted {
+                    let Some(node_id) = run.ready.get(index).cloned() else {
+                        break;
+                    };
+                    if let Some(output) = checkpoint.as_ref().and_then(|c| c.outputs.get(&node_id)) {
+                        debug!("[ericadamsai] Restoring graph node from checkpoint: {}", node_id);
+                        run.ready.remove(index);
+                        let result = NodeResult::restored(node_id, output.clone());
+                        run.finish(graph, result);
+                        continue;
+                    }
+                    let node = graph.nodes[&node_id].clone();
+                    // Loop and subgraph nodes only wait for their nested runs,
+                    // whose nodes hold the permits, so they start even while
+                    // nodes ahead of them wait for one.
+                    let permit = match node.node_type {
+                        NodeType::Aggregator | NodeType::Loop | NodeType::Subgraph => None,
+                        // Nodes needing a permit keep their order behind the
+                        // first one that could not get one.
+                        _ if blocked => {
+                            index += 1;
+                            continue;
+                        }
+                        _ => match reserved.take().or_else(|| permits.clone().try_acquire_owned().ok()) {
+                            Some(permit) => Some(permit),
+                            None => {
+                                blocked = true;
+                                index += 1;
+                                continue;
+                            }
+                        },
+                    };
+                    run.ready.remove(index);
+                    run.order.push(node_id.clone());
+                    debug!("[ericadamsai] Starting graph node: {}", node_id);
+
+                    if let NodeType::Aggregator = node.node_type {
+                        let started_at = chrono::Local::now().to_rfc3339();
+                        let result = AggregateStrategy::from_node(&node)
+                            .map(|strategy| {
+                                let inputs = match strategy {
+                                    AggregateStrategy::FirstSuccess => run.completed_inputs(graph, &node_id),
+                                    _ => run.ordered_inputs(graph, &node_id),
+                                };
+                                strategy.aggregate(inputs)
+                            })
+                            .map_err(|e| NodeError::Failed(e.to_string()));
+                        let result = NodeResult::finished(node_id, result, started_at, 0);
+                        self.save_checkpoint(checkpoint.as_mut(), &result).await;
+                        self.record(&mut run, graph, result);
+                        continue;
+                    }
+
+                    let inputs = run.inputs_for(graph, &node_id);
+                    let executor = self.clone();
+                    let permits = permits.clone();
+                    let started_at = chrono::Local::now().to_rfc3339();
+                    let start = Instant::now();
+                    // Node tasks live in the join set, so dropping the run
+                    // aborts them and releases their permits.
+                    let task = running.spawn(async move {
+                        let _permit = permit;
+                        let result = executor.execute_node(node, inputs, permits).await;
+                        (result, start.elapsed().as_millis() as u64)
+                    });
+                    started.insert(task.id(), (node_id, started_at, start));
+                }
+
+                let joined = if blocked {
+                    // A permit may be freed by one of this run's nodes or by a
+                    // run sharing the permits, so wait for whichever comes first.
+                    tokio::select! {
+                        joined = running.join_next_with_id(), if !running.is_empty() => joined,
+                        permit = permits.clone().acquire_owned() => {
+                            reserved = permit.ok();
+                            continue;
+                        }
+                    }
+                } else {
+                    running.join_next_with_id().await
+                };
+                let Some(joined) = joined else {
+                    break;
+                };
+                // A panic fails the node instead of the whole graph run.
+                let (task, result, duration_ms) = match joined {
+                    Ok((task, (result, duration_ms))) => (task, result, duration_ms),
+                    Err(e) if e.is_panic() => {
+                        let duration_ms = started[&e.id()].2.elapsed().as_millis() as u64;
+                        (e.id(), Err(NodeError::Panicked(e.to_string())), duration_ms)
+                    }
+                    Err(e) => return Err(GraphError::Execution(e.to_string())),
+                };
+                let (node_id, started_at, _) = started.remove(&task).unwrap();
+                let result = NodeResult::finished(node_id, result, started_at, duration_ms);
+                self.save_checkpoint(checkpoint.as_mut(), &result).await;
+                self.record(&mut run, graph, result);
+            }
+
+            run.skip_remaining(graph);
+            let completed = run.results.values().filter(|result| result.is_completed()).count();
+            info!(
+                "[ericadamsai] Graph run finished: {} ({}/{} nodes completed)",
+                graph.id,
+                completed,
+                graph.nodes.len()
+            );
+            graph.execution_order = run.order;
+            Ok(run.results)
+        })
+    }
+
+    fn record(&self, run: &mut GraphRun, graph: &ExecutionGraph, result: NodeResult) {
+        if let NodeStatus::Failed(error) = &result.status {
+            warn!("[ericadamsai] Graph node {} failed: {}", result.node_id, error);
+            if self.config.fail_fast {
+                run.halted = true;
+            }
+        }
+        run.finish(graph, result);
+    }
+
+    async fn execute_node(self, node: GraphNode, inputs: NodeInputs, permits: Arc<Semaphore>) -> Result<NodeOutput, NodeError> {
+        match node.node_type {
+            NodeType::Loop => self.run_loop(&node, inputs, permits).await,
+            NodeType::Subgraph => {
+                let config = SubgraphConfig::from_node(&node).map_err(|e| NodeError::Failed(e.to_string()))?;
+                let outputs = self.run_nested(&config.graph, config.map_inputs(inputs), permits).await?;
+                Ok(config.map_outputs(&outputs))
+            }
+            _ => self.executor.execute(&node, &inputs).await,
+        }
+    }
+
+    /// Run a nested graph and return the outputs of its completed nodes,
+    /// failing if any of its nodes failed
+    async fn run_nested(
+        &self,
+        graph: &ExecutionGraph,
+        seed: NodeInputs,
+        permits: Arc<Semaphore>,
+    ) -> Result<HashMap<String, NodeOutput>, NodeError> {
+        let mut graph = graph.clone();
+        let results = self
+            .run_with_permits(&mut graph, seed, None, permits)
+            .await.map_err(|e| NodeError::Failed(e.to_string()))?;
+        let failed = graph.execution_order.iter().find_map(|id| match &results[id].status {
+            NodeStatus::Failed(error) => Some((id, error)),
+            _ => None,
+        });
+        if let Some((id, error)) = failed {
+            return Err(NodeError::Failed(format!("node {}: {}", id, error)));
+        }
+        Ok(results
+            .into_iter()
+            .filter_map(|(id, result)| Some((id, result.output?)))
+            .collect())
+    }
+
+    /// Run a loop node's body until its `until` condition holds or the
+    /// iteration cap is reached
+    ///
+    /// Each iteration's body receives the loop's inputs, plus the previous
+    /// iteration's output under the loop node's id after the first.
+    async fn run_loop(&self, node: &GraphNode, inputs: NodeInputs, permits: Arc<Semaphore>) -> Result<NodeOutput, NodeError> {
+        let config = LoopConfig::from_node(node).map_err(|e| NodeError::Failed(e.to_string()))?;
+        let until = config.until_condition().map_err(|e| NodeError::Failed(e.to_string()))?;
+
+        let mut previous: Option<NodeOutput> = None;
+        for iteration in 1..=config.max_iterations {
+            let mut seed = inputs.clone();
+            if let Some(previous) = &previous {
+                seed.insert(node.id.clone(), previous.clone());
+            }
+            let outputs = self.run_nested(&config.body, seed, permits.clone()).await.map_err(|e| {
+                NodeError::Failed(format!("iteration {}: {}", iteration, e))
+            })?;
+            let output = config.body_output(&outputs);
+            let done = match &until {
+                Some(condition) => {
+                    let context = ConditionContext { output: Some(&output), metadata: &node.metadata };
+                    condition.evaluate(&context).map_err(|e| NodeError::Failed(e.to_string()))?
+                }
+                None => false,
+            };
+            debug!("[ericadamsai] Loop node {} iteration {} done: {}", node.id, iteration, done);
+            if done {
+                return Ok(output);
+            }
+            previous = Some(output);
+        }
+
+        info!(
+            "[ericadamsai] Loop node {} reached its cap of {} iterations",
+            node.id, config.max_iterations
+        );
+        Ok(previous.unwrap_or_default())
+    }
+}
+
+/// Bookkeeping for one graph run
+struct GraphRun {
+    /// Indices into `graph.edges` of each node's incoming edges
+    incoming: HashMap<String, Vec<usize>>,
+    /// Indices into `graph.edges` of each node's outgoing edges
+    outgoing: HashMap<String, Vec<usize>>,
+    conditions: Vec<Option<Condition>>,
+    /// Inputs for nodes without incoming edges
+    seed: NodeInputs,
+    /// Whether each edge was taken, once its source has finished
+    taken: Vec<bool>,
+    /// Upstream nodes each node is still waiting for
+    remaining: HashMap<String, usize>,
+    /// Nodes that failed or were skipped because of a failure
+    poisoned: HashSet<String>,
+    ready: VecDeque<String>,
+    results: HashMap<String, NodeResult>,
+    /// Position of each finished node in completion order
+    completion: HashMap<String, usize>,
+    order: Vec<String>,
+    halted: bool,
+}
+
+impl GraphRun {
+    fn new(graph: &ExecutionGraph, seed: NodeInputs) -> Result<Self, GraphError> {
+        let mut incoming: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut outgoing: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut conditions = Vec::with_capacity(graph.edges.len());
+        for (index, edge) in graph.edges.iter().enumerate() {
+            incoming.entry(edge.to.clone()).or_default().push(index);
+            outgoing.entry(edge.from.clone()).or_default().push(index);
+            let condition = edge
+                .condition
+                .as_deref()
+                .map(Condition::parse)
+                .transpose()
+                .map_err(|error| GraphError::InvalidCondition {
+                    from: edge.from.clone(),
+                    to: edge.to.clone(),
+                    error,
+                })?;
+            conditions.push(condition);
+        }
+        let remaining: HashMap<String, usize> = graph
+            .nodes
+            .keys()
+            .map(|id| (id.clone(), incoming.get(id).map_or(0, Vec::len)))
+            .collect();
+        let ready = graph
+            .execution_order
+            .iter()
+            .filter(|id| remaining[*id] == 0)
+            .cloned()
+            .collect();
+
+        Ok(Self {
+            incoming,
+            outgoing,
+            conditions,
+            seed,
+            taken: vec![false; graph.edges.len()],
+            remaining,
+            poisoned: HashSet::new(),
+            ready,
+            results: HashMap::new(),
+            completion: HashMap::new(),
+            order: Vec::new(),
+            halted: false,
+        })
+    }
+
+    /// Taken incoming edges of a node whose source produced an output
+    fn taken_inputs<'a>(
+        &'a self,
+        graph: &'a ExecutionGraph,
+        node_id: &str,
+    ) -> impl Iterator<Item = (&'a String, &'a NodeOutput)> {
+        self.incoming
+            .get(node_id)
+            .into_iter()
+            .flatten()
+            .filter(|index| self.taken[**index])
+            .filter_map(|index| {
+                let from = &graph.edges[*index].from;
+                Some((from, self.results.get(from)?.output.as_ref()?))
+            })
+    }
+
+    /// Inputs of a node keyed by upstream node id
+    fn inputs_for(&self, graph: &ExecutionGraph, node_id: &str) -> NodeInputs {
+        if !self.incoming.contains_key(node_id) {
+            return self.seed.clone();
+        }
+        self.taken_inputs(graph, node_id)
+            .map(|(from, output)| (from.clone(), output.clone()))
+            .collect()
+    }
+
+    /// Inputs of a node in incoming edge order, or seed inputs sorted by key
+    fn ordered_inputs(&self, graph: &ExecutionGraph, node_id: &str) -> Vec<NodeOutput> {
+        if !self.incoming.contains_key(node_id) {
+            let mut seed: Vec<_> = self.seed.iter().collect();
+            seed.sort_by(|a, b| a.0.cmp(b.0));
+            return seed.into_iter().map(|(_, output)| output.clone()).collect();
+        }
+        self.taken_inputs(graph, node_id).map(|(_, output)| output.clone()).collect()
+    }
+
+    /// Inputs of a node in the order their upstream nodes completed
+    fn completed_inputs(&self, graph: &ExecutionGraph, node_id: &str) -> Vec<NodeOutput> {
+        if !self.incoming.contains_key(node_id) {
+            return self.ordered_inputs(graph, node_id);
+        }
+        let mut inputs: Vec<_> = self.taken_inputs(graph, node_id).collect();
+        inputs.sort_by_key(|(from, _)| self.completion[*from]);
+        inputs.into_iter().map(|(_, output)| output.clone()).collect()
+    }
+
+    /// Record a finished node and release the nodes waiting on it
+    fn finish(&mut self, graph: &ExecutionGraph, result: NodeResult) {
+        if matches!(result.status, NodeStatus::Failed(_)) {
+            self.poisoned.insert(result.node_id.clone());
+        }
+        let mut released = vec![result.node_id.clone()];
+        self.completion.insert(result.node_id.clone(), self.completion.len());
+        self.results.insert(result.node_id.clone(), result);
+
+        while let Some(node_id) = released.pop() {
+            let outgoing = self.outgoing.get(&node_id).cloned().unwrap_or_default();
+            let choice = self.decision(graph, &node_id, &outgoing);
+            for index in outgoing {
+                self.taken[index] = match choice {
+                    Some(chosen) => chosen == Some(index),
+                    None => self.edge_taken(graph, index),
+                };
+                let next = graph.edges[index].to.clone();
+                let remaining = self.remaining.get_mut(&next).unwrap();
+                *remaining -= 1;
+                if *remaining > 0 {
+                    continue;
+                }
+                if let Some(reason) = self.skip_reason(graph, &next) {
+                    debug!("[ericadamsai] Skipping graph node {}: {}", next, reason);
+                    self.results.insert(next.clone(), NodeResult::skipped(&next, reason));
+                    released.push(next);
+                } else {
+                    self.ready.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Edge chosen by a completed decision node: the first whose condition
+    /// holds, else the first without a condition; `None` for other nodes
+    fn decision(&self, graph: &ExecutionGraph, node_id: &str, outgoing: &[usize]) -> Option<Option<usize>> {
+        let is_decision = matches!(graph.nodes[node_id].node_type, NodeType::Decision);
+        if !is_decision || !self.results[node_id].is_completed() {
+            return None;
+        }
+        let chosen = outgoing
+            .iter()
+            .find(|index| self.conditions[**index].is_some() && self.edge_taken(graph, **index))
+            .or_else(|| outgoing.iter().find(|index| self.conditions[**index].is_none()))
+            .copied();
+        let target = chosen.map(|index| &graph.edges[index].to);
+        debug!("[ericadamsai] Decision node {} chose edge to {:?}", node_id, target);
+        Some(chosen)
+    }
+
+    fn edge_taken(&self, graph: &ExecutionGraph, index: usize) -> bool {
+        let edge = &graph.edges[index];
+        let source = &self.results[&edge.from];
+        if !source.is_completed() {
+            return false;
+        }
+        let Some(condition) = &self.conditions[index] else {
+            return true;
+        };
+        let context = ConditionContext {
+            output: source.output.as_ref(),
+            metadata: &graph.nodes[&edge.from].metadata,
+        };
+        match condition.evaluate(&context) {
+            Ok(taken) => {
+                debug!("[ericadamsai] Edge {} -> {} condition `{}`: {}", edge.from, edge.to, condition, taken);
+                taken
+            }
+            Err(e) => {
+                warn!("[ericadamsai] Edge {} -> {} not taken: {}", edge.from, edge.to, e);
+                false
+            }
+        }
+    }
+
+    /// Why a node whose upstream nodes have all finished should not run
+    ///
+    /// Aggregators still run when some upstream nodes failed, as long as
+    /// one of their incoming edges was taken.
+    fn skip_reason(&mut self, graph: &ExecutionGraph, node_id: &str) -> Option<String> {
+        let incoming = &self.incoming[node_id];
+        let poisoned = incoming
+            .iter()
+            .map(|index| &graph.edges[*index].from)
+            .find(|from| self.poisoned.contains(*from))
+            .cloned();
+        let aggregator = matches!(graph.nodes[node_id].node_type, NodeType::Aggregator);
+        let any_taken = incoming.iter().any(|index| self.taken[*index]);
+
+        let reason = match poisoned {
+            Some(from) if !aggregator || !any_taken => format!("upstream node {} did not complete", from),
+            _ if !any_taken => return Some("no incoming edge was taken".to_string()),
+            _ => return None,
+        };
+        self.poisoned.insert(node_id.to_string());
+        Some(reason)
+    }
+
+    /// Mark nodes that never got to run after the run was halted
+    fn skip_remaining(&mut self, graph: &ExecutionGraph) {
+        for node_id in &graph.execution_order {
+            if !self.results.contains_key(node_id) {
+                let reason = "graph run halted after a node failed".to_string();
+                self.results.insert(node_id.clone(), NodeResult::skipped(node_id, reason));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::graph::{plain_edge, task_node, GraphEdge, NodeType};
+
+    /// Adds the node's `value` to the sum of its inputs
+    struct SumExecutor {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl NodeExecutor for SumExecutor {
+        async fn execute(&self, node: &GraphNode, inputs: &NodeInputs) -> Result<NodeOutput, NodeError> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            if node.metadata["panic"] == serde_json::json!(true) {
+                panic!("{} panicked", node.id);
+            }
+            if node.metadata["fail"] == serde_json::json!(true) {
+                return Err(NodeError::Failed(format!("{} failed", node.id)));
+            }
+            let sum: i64 = inputs.values().filter_map(|v| v.as_i64()).sum();
+            Ok(serde_json::json!(sum + node.metadata["value"].as_i64().unwrap_or(0)))
+        }
+    }
+
+    fn node(id: &str, metadata: serde_json::Value) -> GraphNode {
+        GraphNode { metadata, ..task_node(id) }
+    }
+
+    fn diamond(fail_left: bool) -> ExecutionGraph {
+        let mut graph = ExecutionGraph::new("diamond".to_string());
+        graph.add_node(node("source", serde_json::json!({"value": 1}))).unwrap();
+        graph.add_node(node("left", serde_json::json!({"value": 10, "fail": fail_left}))).unwrap();
+        graph.add_node(node("right", serde_json::json!({"value": 100}))).unwrap();
+        graph.add_node(node("sink", serde_json::json!({"value": 1000}))).unwrap();
+        for (from, to) in [("source", "left"), ("source", "right"), ("left", "sink"), ("right", "sink")] {
+            graph.add_edge(plain_edge(from, to)).unwrap();
+        }
+        graph
+    }
+
+    fn sum_executor() -> Arc<SumExecutor> {
+        Arc::new(SumExecutor {
+            running: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_run_passes_outputs_downstream() {
+        let nodes = sum_executor();
+        let executor = GraphExecutor::new(nodes.clone());
+        let mut graph = diamond(false);
+
+        let results = executor.run(&mut graph).await.unwrap();
+        assert_eq!(results["left"].output, Some(serde_json::json!(11)));
+        assert_eq!(results["right"].output, Some(serde_json::json!(101)));
+        assert_eq!(results["sink"].output, Some(serde_json::json!(1112)));
+        assert_eq!(nodes.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(graph.execution_order.first().unwrap(), "source");
+        assert_eq!(graph.execution_order.last().unwrap(), "sink");
+        assert_eq!(graph.execution_order.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_run_stops_its_nodes() {
+        let nodes = sum_executor();
+        let executor = GraphExecutor::new(nodes.clone());
+        let mut graph = diamond(false);
+
+        let run = executor.run(&mut graph);
+        assert!(tokio::time::timeout(tokio::time::Duration::from_millis(5), run).await.is_err());
+        tokio::time::sleep(tokio::time::Duration::from_millis(40)).await;
+        // `source` was aborted mid-sleep, so it never left the running count.
+        assert_eq!(nodes.running.load(Ordering::SeqCst), 1);
+        assert_eq!(nodes.peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permit_free_nodes_do_not_wait_for_permits() {
+        let mut graph = ExecutionGraph::new("mixed".to_string());
+        graph.add_node(node("a", serde_json::json!({"value": 1}))).unwrap();
+        graph.add_node(node("b", serde_json::json!({"value": 2}))).unwrap();
+        graph.add_node(GraphNode { node_type: NodeType::Aggregator, ..task_node("z") }).unwrap();
+
+        let config = GraphExecutorConfig { max_concurrency: 1, fail_fast: false };
+        let results = GraphExecutor::with_config(sum_executor(), config).run(&mut graph).await.unwrap();
+        assert!(results.values().all(NodeResult::is_completed));
+        // `b` waits for the permit `a` holds, but the aggregator needs none.
+        assert_eq!(graph.execution_order, vec!["a", "z", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_node_skips_dependents() {
+        let nodes = sum_executor();
+        let config = GraphExecutorConfig { max_concurrency: 1, fail_fast: false };
+        let executor = GraphExecutor::with_config(nodes.clone(), config);
+        let mut graph = diamond(true);
+
+        let results = executor.run(&mut graph).await.unwrap();
+        assert_eq!(results["left"].status, NodeStatus::Failed("node execution failed: left failed".to_string()));
+        assert!(results["right"].is_completed());
+        assert!(matches!(results["sink"].status, NodeStatus::Skipped(_)));
+        assert_eq!(nodes.peak.load(Ordering::SeqCst), 1);
+        assert_eq!(graph.execution_order.len(), 3);
+
+        graph.nodes.get_mut("left").unwrap().metadata = serde_json::json!({"panic": true});
+        let results = executor.run(&mut graph).await.unwrap();
+        assert!(matches!(results["left"].status, NodeStatus::Failed(ref error) if error.contains("panicked")));
+        assert!(results["right"].is_completed());
+    }
+
+    #[tokio::test]
+    async fn test_untaken_branch_is_skipped() {
+        let mut graph = ExecutionGraph::new("branch".to_string());
+        graph.add_node(node("score", serde_json::json!({"value": 80}))).unwrap();
+        graph.add_node(node("high", serde_json::json!({"value": 1}))).unwrap();
+        graph.add_node(node("low", serde_json::json!({"value": 2}))).unwrap();
+        graph.add_node(node("low-report", serde_json::json!({}))).unwrap();
+        graph.add_node(node("join", serde_json::json!({}))).unwrap();
+        for (from, to, condition) in [
+            ("score", "high", Some("output > 50")),
+            ("score", "low", Some("output <= 50")),
+            ("low", "low-report", None),
+            ("high", "join", None),
+            ("low", "join", None),
+        ] {
+            graph
+                .add_edge(GraphEdge { condition: condition.map(str::to_string), ..plain_edge(from, to) })
+                .unwrap();
+        }
+
+        let results = GraphExecutor::new(sum_executor()).run(&mut graph).await.unwrap();
+        assert_eq!(results["high"].output, Some(serde_json::json!(81)));
+        assert_eq!(results["low"].status, NodeStatus::Skipped("no incoming edge was taken".to_string()));
+        assert!(matches!(results["low-report"].status, NodeStatus::Skipped(_)));
+        assert_eq!(results["join"].output, Some(serde_json::json!(81)));
+
+        graph.edges[0].condition = Some("output >".to_string());
+        let error = GraphExecutor::new(sum_executor()).run(&mut graph).await.unwrap_err();
+        assert_eq!(error.node_ids(), vec!["score", "high"]);
+        assert!(matches!(error, GraphError::InvalidCondition { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_decision_loop_and_aggregator_nodes() {
+        let mut body = ExecutionGraph::new("body".to_string());
+        body.add_node(node("increment", serde_json::json!({"value": 1}))).unwrap();
+
+        let mut graph = ExecutionGraph::new("typed".to_string());
+        let route = node("route", serde_json::json!({"value": 5}));
+        graph.add_node(GraphNode { node_type: NodeType::Decision, ..route }).unwrap();
+        for id in ["large", "medium", "fallback"] {
+            graph.add_node(node(id, serde_json::json!({}))).unwrap();
+        }
+        let loop_metadata = serde_json::json!({"loop": {"body": body, "until": "output >= 3"}});
+        graph.add_node(GraphNode { node_type: NodeType::Loop, ..node("count", loop_metadata) }).unwrap();
+        graph.add_node(node("broken", serde_json::json!({"fail": true}))).unwrap();
+        let strategy = serde_json::json!({"strategy": "concat"});
+        graph.add_node(GraphNode { node_type: NodeType::Aggregator, ..node("all", strategy) }).unwrap();
+        let strategy = serde_json::json!({"strategy": "first_success"});
+        graph.add_node(GraphNode { node_type: NodeType::Aggregator, ..node("first", strategy) }).unwrap();
+        for (from, to, condition) in [
+            ("route", "large", Some("output > 10")),
+            ("route", "medium", Some("output > 3")),
+            ("route", "fallback", None),
+            ("medium", "all", None),
+            ("count", "all", None),
+            ("broken", "all", None),
+            ("count", "first", None),
+            ("medium", "first", None),
+        ] {
+            graph
+                .add_edge(GraphEdge { condition: condition.map(str::to_string), ..plain_edge(from, to) })
+                .unwrap();
+        }
+
+        let results = GraphExecutor::new(sum_executor()).run(&mut graph).await.unwrap();
+        assert!(results["medium"].is_completed());
+        assert!(matches!(results["large"].status, NodeStatus::Skipped(_)));
+        assert!(matches!(results["fallback"].status, NodeStatus::Skipped(_)));
+        assert_eq!(results["count"].output, Some(serde_json::json!(3)));
+        assert_eq!(results["all"].output, Some(serde_json::json!([5, 3])));
+        // `medium` finishes before the three loop iterations of `count`.
+        assert_eq!(results["first"].output, Some(serde_json::json!(5)));
+
+        let mut seeded = ExecutionGraph::new("seeded".to_string());
+        seeded.add_node(GraphNode { node_type: NodeType::Aggregator, ..node("all", serde_json::json!({})) }).unwrap();
+        let seed = [("b", 2), ("c", 3), ("a", 1)]
+            .into_iter()
+            .map(|(id, value)| (id.to_string(), serde_json::json!(value)))
+            .collect();
+        let results = GraphExecutor::new(sum_executor()).run_seeded(&mut seeded, seed, None).await.unwrap();
+        assert_eq!(results["all"].output, Some(serde_json::json!([1, 2, 3])));
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_node_maps_inputs_and_outputs() {
+        let mut inner = ExecutionGraph::new("inner".to_string());
+        inner.add_node(node("double", serde_json::json!({"value": 2}))).unwrap();
+        inner.add_node(node("total", serde_json::json!({"value": 0}))).unwrap();
+        inner.add_edge(plain_edge("double", "total")).unwrap();
+        let mut config = SubgraphConfig::new(inner);
+        config.inputs.insert("source".to_string(), "seed".to_string());
+        config.outputs.insert("sum".to_string(), "total".to_string());
+
+        let mut graph = ExecutionGraph::new("outer".to_string());
+        graph.add_node(node("source", serde_json::json!({"value": 40}))).unwrap();
+        graph.add_node(config.into_node("nested")).unwrap();
+        graph.add_edge(plain_edge("source", "nested")).unwrap();
+
+        let results = GraphExecutor::new(sum_executor()).run(&mut graph).await.unwrap();
+        assert_eq!(results["nested"].output, Some(serde_json::json!({"sum": 42})));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_covers_nested_runs() {
+        let mut inner = ExecutionGraph::new("inner".to_string());
+        for id in ["x", "y", "z"] {
+            inner.add_node(node(id, serde_json::json!({"value": 1}))).unwrap();
+        }
+        let mut graph = ExecutionGraph::new("outer".to_string());
+        graph.add_node(node("a", serde_json::json!({"value": 1}))).unwrap();
+        graph.add_node(SubgraphConfig::new(inner.clone()).into_node("nested-1")).unwrap();
+        graph.add_node(SubgraphConfig::new(inner).into_node("nested-2")).unwrap();
+
+        let nodes = sum_executor();
+        let config = GraphExecutorConfig { max_concurrency: 2, fail_fast: false };
+        let results = GraphExecutor::with_config(nodes.clone(), config).run(&mut graph).await.unwrap();
+        assert!(results.values().all(NodeResult::is_completed));
+        assert_eq!(nodes.peak.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,188 @@
+//! Graph Export - ericadamsai watermark
+//! Renders execution graphs as Graphviz DOT and Mermaid flowcharts
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{ExecutionGraph, GraphNode, NodeResult, NodeStatus, NodeType};
+
+/// Class name, fill color and stroke color for each run status
+const STATUS_STYLES: [(&str, &str, &str); 3] = [
+    ("completed", "#c8e6c9", "#2e7d32"),
+    ("failed", "#ffcdd2", "#c62828"),
+    ("skipped", "#eeeeee", "#9e9e9e"),
+];
+
+/// Style of a node's run status, if it ran
+fn status_style(result: Option<&NodeResult>) -> Option<(&'static str, &'static str, &'static str)> {
+    let index = match result?.status {
+        NodeStatus::Completed => 0,
+        NodeStatus::Failed(_) => 1,
+        NodeStatus::Skipped(_) => 2,
+    };
+    Some(STATUS_STYLES[index])
+}
+
+impl ExecutionGraph {
+    /// Render the graph as a Graphviz DOT digraph
+    ///
+    /// With run results, nodes are filled green, red or grey when they
+    /// completed, failed or were skipped.
+    pub fn to_dot(&self, results: Option<&HashMap<String, NodeResult>>) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph {} {{", dot_quote(&self.id));
+        let _ = writeln!(dot, "  rankdir=LR;");
+        for node in self.sorted_nodes() {
+            let shape = match node.node_type {
+                NodeType::Task => "box",
+                NodeType::Decision => "diamond",
+                NodeType::Loop => "hexagon",
+                NodeType::Aggregator => "trapezium",
+                NodeType::Subgraph => "box3d",
+            };
+            let mut attributes = format!("label={}, shape={}", dot_quote(&node.name), shape);
+            if let Some((_, fill, stroke)) = status_style(results.and_then(|r| r.get(&node.id))) {
+                let _ = write!(attributes, ", style=filled, fillcolor=\"{}\", color=\"{}\"", fill, stroke);
+            }
+            let _ = writeln!(dot, "  {} [{}];", dot_quote(&node.id), attributes);
+        }
+        for edge in &self.edges {
+            let label = edge
+                .condition
+                .as_ref()
+                .map(|condition| format!(" [label={}]", dot_quote(condition)))
+                .unwrap_or_default();
+            let _ = writeln!(dot, "  {} -> {}{};", dot_quote(&edge.from), dot_quote(&edge.to), label);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as a Mermaid flowchart
+    ///
+    /// Node ids are replaced by `n0`, `n1`, ... since Mermaid ids cannot hold
+    /// arbitrary characters. With run results, nodes are styled by status as
+    /// in [`ExecutionGraph::to_dot`].
+    pub fn to_mermaid(&self, results: Option<&HashMap<String, NodeResult>>) -> String {
+        let nodes = self.sorted_nodes();
+        let ids: HashMap<&str, String> = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id.as_str(), format!("n{}", index)))
+            .collect();
+
+        let mut mermaid = String::from("flowchart LR\n");
+        let mut classes: HashMap<&str, Vec<&str>> = HashMap::new();
+        for node in &nodes {
+            let id = &ids[node.id.as_str()];
+            let label = mermaid_quote(&node.name);
+            let shape = match node.node_type {
+                NodeType::Task => format!("[{}]", label),
+                NodeType::Decision => format!("{{{}}}", label),
+                NodeType::Loop => format!("{{{{{}}}}}", label),
+                NodeType::Aggregator => format!("[/{}\\]", label),
+                NodeType::Subgraph => format!("[[{}]]", label),
+            };
+            let _ = writeln!(mermaid, "  {}{}", id, shape);
+            if let Some((class, _, _)) = status_style(results.and_then(|r| r.get(&node.id))) {
+                classes.entry(class).or_default().push(id);
+            }
+        }
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str())) else {
+                continue;
+            };
+            match &edge.condition {
+                Some(condition) => {
+                    let _ = writeln!(mermaid, "  {} -->|{}| {}", from, mermaid_quote(condition), to);
+                }
+                None => {
+                    let _ = writeln!(mermaid, "  {} --> {}", from, to);
+                }
+            }
+        }
+
+        for (class, fill, stroke) in STATUS_STYLES {
+            if let Some(members) = classes.get(class) {
+                let _ = writeln!(mermaid, "  classDef {} fill:{},stroke:{};", class, fill, stroke);
+                let _ = writeln!(mermaid, "  class {} {};", members.join(","), class);
+            }
+        }
+        mermaid
+    }
+
+    fn sorted_nodes(&self) -> Vec<&GraphNode> {
+        let mut nodes: Vec<&GraphNode> = self.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes
+    }
+}
+
+fn dot_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "#quot;"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{plain_edge, task_node, GraphEdge};
+
+    fn graph() -> ExecutionGraph {
+        let mut graph = ExecutionGraph::new("review".to_string());
+        for (id, node_type) in [("fetch", NodeType::Task), ("route", NodeType::Decision), ("say \"hi\"", NodeType::Task)] {
+            graph.add_node(GraphNode { node_type, ..task_node(id) }).unwrap();
+        }
+        for (from, to, condition) in [("fetch", "route", None), ("route", "say \"hi\"", Some("score > 0.5"))] {
+            graph
+                .add_edge(GraphEdge { condition: condition.map(str::to_string), ..plain_edge(from, to) })
+                .unwrap();
+        }
+        graph
+    }
+
+    fn results() -> HashMap<String, NodeResult> {
+        let result = |node_id: &str, status| NodeResult {
+            node_id: node_id.to_string(),
+            status,
+            output: None,
+            started_at: None,
+            finished_at: None,
+            duration_ms: 0,
+        };
+        HashMap::from([
+            ("fetch".to_string(), result("fetch", NodeStatus::Completed)),
+            ("route".to_string(), result("route", NodeStatus::Failed("boom".to_string()))),
+        ])
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = graph().to_dot(Some(&results()));
+        assert!(dot.starts_with("digraph \"review\" {\n  rankdir=LR;\n"));
+        assert!(dot.contains(r##""fetch" [label="fetch", shape=box, style=filled, fillcolor="#c8e6c9", color="#2e7d32"];"##));
+        assert!(dot.contains(r##""route" [label="route", shape=diamond, style=filled, fillcolor="#ffcdd2""##));
+        assert!(dot.contains(r#""say \"hi\"" [label="say \"hi\"", shape=box];"#));
+        assert!(dot.contains(r#""route" -> "say \"hi\"" [label="score > 0.5"];"#));
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let expected = r##"flowchart LR
+  n0["fetch"]
+  n1{"route"}
+  n2["say #quot;hi#quot;"]
+  n0 --> n1
+  n1 -->|"score > 0.5"| n2
+  classDef completed fill:#c8e6c9,stroke:#2e7d32;
+  class n0 completed;
+  classDef failed fill:#ffcdd2,stroke:#c62828;
+  class n1 failed;
+"##;
+        assert_eq!(graph().to_mermaid(Some(&results())), expected);
+        assert!(!graph().to_mermaid(None).contains("classDef"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Graph Import - ericadamsai watermark
+//! Loads execution graphs from YAML and TOML authoring documents
+//!
+//! ```yaml
+//! id: review
+//! nodes:
+//!   - id: fetch
+//!     metadata: { url: "https://example.com" }
+//!     outputs: [body]
+//!   - id: score
+//!     depends_on: [fetch]
+//!     inputs: [body]
+//!   - id: route
+//!     type: decision
+//!     depends_on: [score]
+//!   - id: escalate
+//! edges:
+//!   - from: route
+//!     to: escalate
+//!     when: "score < 0.5"
+//! ```
+//!
+//! `depends_on` adds an unconditional edge from each listed node; `edges`
+//! adds edges that need a condition. Each of a node's `inputs` must be among
+//! the `outputs` of at least one node with an edge into it. Unknown fields
+//! are rejected so typos do not go unnoticed.
+
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{ExecutionGraph, GraphEdge, GraphError, GraphNode, NodeType};
+
+/// Authoring document describing a graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GraphSpec {
+    pub id: String,
+    pub nodes: Vec<NodeSpec>,
+    #[serde(default)]
+    pub edges: Vec<EdgeSpec>,
+}
+
+/// Node entry of a graph document
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeSpec {
+    pub id: String,
+    /// Display name; defaults to the id
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(rename = "type", default = "default_node_type")]
+    pub node_type: NodeType,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Names of the values the node produces
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// Names of the values the node expects from each upstream node
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    #[serde(default = "empty_metadata")]
+    pub metadata: serde_json::Value,
+}
+
+/// Edge entry of a graph document
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EdgeSpec {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub when: Option<String>,
+}
+
+fn default_node_type() -> NodeType {
+    NodeType::Task
+}
+
+fn empty_metadata() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+impl GraphSpec {
+    /// Build and validate the graph the document describes
+    pub fn into_graph(self) -> Result<ExecutionGraph, GraphError> {
+        let mut graph = ExecutionGraph::new(self.id);
+        for node in self.nodes {
+            graph.add_node(GraphNode {
+                name: node.name.unwrap_or_else(|| node.id.clone()),
+                id: node.id,
+                node_type: node.node_type,
+                dependencies: node.depends_on,
+                outputs: node.outputs,
+                inputs: node.inputs,
+                metadata: node.metadata,
+            })?;
+        }
+        for edge in self.edges {
+            graph.add_edge(GraphEdge {
+                from: edge.from,
+                to: edge.to,
+                condition: edge.when,
+            })?;
+        }
+        graph.reconcile()?;
+        graph.validate()?;
+        graph.waves()?;
+        Ok(graph)
+    }
+}
+
+impl ExecutionGraph {
+    /// Load a graph from a YAML document
+    pub fn from_yaml(source: &str) -> Result<Self, GraphError> {
+        let spec: GraphSpec = serde_yaml::from_str(source).map_err(|e| GraphError::InvalidDocument {
+            format: "yaml".to_string(),
+            message: e.to_string(),
+        })?;
+        spec.into_graph()
+    }
+
+    /// Load a graph from a TOML document
+    pub fn from_toml(source: &str) -> Result<Self, GraphError> {
+        let spec: GraphSpec = toml::from_str(source).map_err(|e| GraphError::InvalidDocument {
+            format: "toml".to_string(),
+            message: e.to_string(),
+        })?;
+        spec.into_graph()
+    }
+
+    /// Load a graph from a `.yaml`, `.yml` or `.toml` file
+    pub fn from_file(path: &Path) -> Result<Self, GraphError> {
+        let invalid = |message: String| GraphError::InvalidDocument {
+            format: path.display().to_string(),
+            message,
+        };
+        let source = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        let graph = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => Self::from_yaml(&source)?,
+            Some("toml") => Self::from_toml(&source)?,
+            _ => return Err(invalid("expected a .yaml, .yml or .toml file".to_string())),
+        };
+        info!("[ericadamsai] Loaded graph {} from {}", graph.id, path.display());
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_yaml_and_toml() {
+        let yaml = r#"
+id: review
+nodes:
+  - id: fetch
+    metadata: { url: "https://example.com" }
+    outputs: [body]
+  - id: score
+    depends_on: [fetch]
+    inputs: [body]
+  - id: route
+    type: decision
+    depends_on: [score]
+  - id: escalate
+edges:
+  - from: route
+    to: escalate
+    when: "score < 0.5"
+"#;
+        let toml = r#"
+id = "review"
+
+[[nodes]]
+id = "fetch"
+metadata = { url = "https://example.com" }
+outputs = ["body"]
+
+[[nodes]]
+id = "score"
+depends_on = ["fetch"]
+inputs = ["body"]
+
+[[nodes]]
+id = "route"
+type = "Decision"
+depends_on = ["score"]
+
+[[nodes]]
+id = "escalate"
+
+[[edges]]
+from = "route"
+to = "escalate"
+when = "score < 0.5"
+"#;
+        for mut graph in [ExecutionGraph::from_yaml(yaml).unwrap(), ExecutionGraph::from_toml(toml).unwrap()] {
+            assert_eq!(graph.nodes.len(), 4);
+            assert_eq!(graph.nodes["route"].node_type, NodeType::Decision);
+            assert_eq!(graph.nodes["fetch"].outputs, vec!["body"]);
+            assert_eq!(graph.nodes["escalate"].dependencies, vec!["route"]);
+            assert_eq!(graph.nodes["fetch"].metadata["url"], "https://example.com");
+            assert_eq!(graph.edges.len(), 3);
+            graph.topological_sort().unwrap();
+            assert_eq!(graph.execution_order, vec!["fetch", "score", "route", "escalate"]);
+        }
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let typo = "id: g\nnodes:\n  - id: a\n    depnds_on: [b]\n";
+        let error = ExecutionGraph::from_yaml(typo).unwrap_err();
+        assert!(error.to_string().contains("unknown field `depnds_on`"), "{}", error);
+
+        let unknown_type = "id: g\nnodes:\n  - id: a\n    type: fork\n";
+        assert!(matches!(
+            ExecutionGraph::from_yaml(unknown_type),
+            Err(GraphError::InvalidDocument { .. })
+        ));
+
+        let dangling = "id = \"g\"\n[[nodes]]\nid = \"a\"\ndepends_on = [\"missing\"]\n";
+        assert!(matches!(
+            ExecutionGraph::from_toml(dangling),
+            Err(GraphError::DanglingEdge { .. })
+        ));
+
+        let cycle = "id: g\nnodes:\n  - id: a\n    depends_on: [b]\n  - id: b\n    depends_on: [a]\n";
+        assert!(matches!(ExecutionGraph::from_yaml(cycle), Err(GraphError::Cycle { .. })));
+
+        let mismatched = "id: g\nnodes:\n  - id: a\n    outputs: [text]\n  - id: b\n    depends_on: [a]\n    inputs: [body]\n";
+        assert!(matches!(ExecutionGraph::from_yaml(mismatched), Err(GraphError::MissingInput { .. })));
+    }
+}
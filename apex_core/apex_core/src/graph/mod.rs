@@ -0,0 +1,530 @@
+//! Graph Execution Module - ericadamsai watermark
+//! Handles directed acyclic graph (DAG) based task execution and orchestration
+
+pub mod checkpoint;
+pub mod condition;
+pub mod error;
+pub mod executor;
+pub mod export;
+pub mod import;
+pub mod nodes;
+pub mod patch;
+pub mod schedule;
+pub mod subgraph;
+pub mod template;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug};
+
+pub use checkpoint::GraphCheckpoint;
+pub use condition::{Condition, ConditionContext, ConditionError};
+pub use error::GraphError;
+pub use executor::{
+    GraphExecutor, GraphExecutorConfig, NodeError, NodeExecutor, NodeInputs, NodeOutput, NodeResult,
+    NodeStatus,
+};
+pub use import::{EdgeSpec, GraphSpec, NodeSpec};
+pub use nodes::{AggregateStrategy, LoopConfig};
+pub use patch::GraphPatch;
+pub use schedule::{NodeEstimate, NodeTiming, ScheduleEstimate};
+pub use subgraph::SubgraphConfig;
+pub use template::{GraphTemplate, TemplateParameter};
+
+/// Represents a node in the execution graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub name: String,
+    pub node_type: NodeType,
+    /// Ids of the nodes with an edge into this one
+    pub dependencies: Vec<String>,
+    /// Names of the values this node produces
+    pub outputs: Vec<String>,
+    /// Names of the values this node expects from each upstream node
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    pub metadata: serde_json::Value,
+}
+
+impl GraphNode {
+    /// Scheduling priority from `metadata.priority`; higher runs first
+    pub fn priority(&self) -> i64 {
+        self.metadata.get("priority").and_then(|p| p.as_i64()).unwrap_or(0)
+    }
+}
+
+/// Type of node in the graph; see [`nodes`] for how each type runs
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum NodeType {
+    #[serde(alias = "task")]
+    Task,
+    #[serde(alias = "decision")]
+    Decision,
+    #[serde(alias = "loop")]
+    Loop,
+    #[serde(alias = "aggregator")]
+    Aggregator,
+    #[serde(alias = "subgraph")]
+    Subgraph,
+}
+
+/// Represents an edge in the execution graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    /// Expression deciding at runtime whether the edge is taken; see
+    /// [`condition`] for the syntax
+    pub condition: Option<String>,
+}
+
+/// Directed acyclic graph for execution
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionGraph {
+    pub id: String,
+    pub nodes: HashMap<String, GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub execution_order: Vec<String>,
+}
+
+impl ExecutionGraph {
+    /// Create a new execution graph
+    pub fn new(id: String) -> Self {
+        info!("[ericadamsai] Creating ExecutionGraph: {}", id);
+        Self {
+            id,
+            nodes: HashMap::new(),
+            edges: Vec::new(),
+            execution_order: Vec::new(),
+        }
+    }
+
+    /// Add a node to the graph
+    pub fn add_node(&mut self, node: GraphNode) -> Result<(), GraphError> {
+        if self.nodes.contains_key(&node.id) {
+            return Err(GraphError::DuplicateNode { node_id: node.id });
+        }
+        debug!("[ericadamsai] Adding node to graph: {}", node.id);
+        self.nodes.insert(node.id.clone(), node);
+        self.execution_order.clear();
+        Ok(())
+    }
+
+    /// Add an edge between two nodes, recording the source in the target's
+    /// `dependencies`
+    pub fn add_edge(&mut self, edge: GraphEdge) -> Result<(), GraphError> {
+        self.check_edge(&edge)?;
+        if self.edges.iter().any(|e| e.from == edge.from && e.to == edge.to) {
+            return Err(GraphError::DuplicateEdge { from: edge.from, to: edge.to });
+        }
+        debug!("[ericadamsai] Adding edge: {} -> {}", edge.from, edge.to);
+        let target = self.nodes.get_mut(&edge.to).unwrap();
+        if !target.dependencies.contains(&edge.from) {
+            target.dependencies.push(edge.from.clone());
+        }
+        self.edges.push(edge);
+        self.execution_order.clear();
+        Ok(())
+    }
+
+    /// Topologically sort the graph and determine execution order
+    ///
+    /// Among nodes whose dependencies are satisfied, nodes with a higher
+    /// `metadata.priority` come first, then nodes with the smaller id, so the
+    /// order is the same on every run.
+    pub fn topological_sort(&mut self) -> Result<(), GraphError> {
+        let (mut in_degree, successors) = self.adjacency();
+        let mut ready: BinaryHeap<(i64, Reverse<&str>)> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| (self.nodes[*id].priority(), Reverse(*id)))
+            .collect();
+
+        let mut sorted_order = Vec::with_capacity(self.nodes.len());
+        while let Some((_, Reverse(node_id))) = ready.pop() {
+            sorted_order.push(node_id.to_string());
+            for next in &successors[node_id] {
+                let degree = in_degree.get_mut(next).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push((self.nodes[*next].priority(), Reverse(*next)));
+                }
+            }
+        }
+
+        if sorted_order.len() != self.nodes.len() {
+            return Err(GraphError::Cycle { nodes: self.find_cycle(&in_degree) });
+        }
+
+        self.execution_order = sorted_order;
+        info!("[ericadamsai] Graph topologically sorted");
+        Ok(())
+    }
+
+    /// Group nodes into waves that can run in parallel
+    ///
+    /// Each wave holds the nodes whose dependencies are all in earlier waves,
+    /// ordered like [`ExecutionGraph::topological_sort`].
+    pub fn waves(&self) -> Result<Vec<Vec<String>>, GraphError> {
+        let (mut in_degree, successors) = self.adjacency();
+        let mut wave: Vec<&str> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut waves = Vec::new();
+        let mut placed = 0;
+        while !wave.is_empty() {
+            wave.sort_by_key(|id| (Reverse(self.nodes[*id].priority()), *id));
+            let mut next_wave = Vec::new();
+            for node_id in &wave {
+                for next in &successors[node_id] {
+                    let degree = in_degree.get_mut(next).unwrap();
+                    *degree -= 1;
+                    if *degree == 0 {
+                        next_wave.push(*next);
+                    }
+                }
+            }
+            placed += wave.len();
+            waves.push(wave.iter().map(|id| id.to_string()).collect());
+            wave = next_wave;
+        }
+
+        if placed != self.nodes.len() {
+            return Err(GraphError::Cycle { nodes: self.find_cycle(&in_degree) });
+        }
+        Ok(waves)
+    }
+
+    /// In-degree and successors of every node
+    fn adjacency(&self) -> (HashMap<&str, usize>, HashMap<&str, Vec<&str>>) {
+        let mut in_degree: HashMap<&str, usize> = self.nodes.keys().map(|id| (id.as_str(), 0)).collect();
+        let mut successors: HashMap<&str, Vec<&str>> =
+            self.nodes.keys().map(|id| (id.as_str(), Vec::new())).collect();
+        for edge in &self.edges {
+            let next = successors.get_mut(edge.from.as_str());
+            let degree = in_degree.get_mut(edge.to.as_str());
+            if let (Some(next), Some(degree)) = (next, degree) {
+                next.push(edge.to.as_str());
+                *degree += 1;
+            }
+        }
+        (in_degree, successors)
+    }
+
+    /// Nodes of one cycle among the nodes Kahn's algorithm could not place,
+    /// in edge order starting from the smallest id
+    ///
+    /// Every unplaced node has an unplaced predecessor, so walking
+    /// predecessors from any of them must eventually revisit a node.
+    fn find_cycle(&self, in_degree: &HashMap<&str, usize>) -> Vec<String> {
+        let unplaced = |id: &str| in_degree.get(id).is_some_and(|degree| *degree > 0);
+        let Some(start) = in_degree.keys().copied().filter(|id| unplaced(id)).min() else {
+            return Vec::new();
+        };
+
+        let mut path: Vec<&str> = vec![start];
+        loop {
+            let current = *path.last().unwrap();
+            let previous = self
+                .edges
+                .iter()
+                .filter(|edge| edge.to == current && unplaced(&edge.from))
+                .map(|edge| edge.from.as_str())
+                .min()
+                .unwrap();
+            if let Some(position) = path.iter().position(|id| *id == previous) {
+                let mut cycle: Vec<String> = path[position..].iter().rev().map(|id| id.to_string()).collect();
+                let smallest = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap();
+                cycle.rotate_left(smallest);
+                return cycle;
+            }
+            path.push(previous);
+        }
+    }
+
+    /// Get execution order
+    pub fn get_execution_order(&self) -> Vec<String> {
+        self.execution_order.clone()
+    }
+
+    /// Validate graph integrity
+    ///
+    /// Besides checking edges and node configuration, this checks that each
+    /// node's `dependencies` list exactly the sources of its incoming edges,
+    /// and that every `inputs` entry of a node with incoming edges is among
+    /// the `outputs` of at least one of their sources.
+    pub fn validate(&self) -> Result<(), GraphError> {
+        let mut node_ids: Vec<&String> = self.nodes.keys().collect();
+        node_ids.sort();
+        for node_id in &node_ids {
+            nodes::validate_node(&self.nodes[*node_id])?;
+        }
+
+        let mut seen = HashSet::new();
+        for edge in &self.edges {
+            self.check_edge(edge)?;
+            if !seen.insert((&edge.from, &edge.to)) {
+                return Err(GraphError::DuplicateEdge { from: edge.from.clone(), to: edge.to.clone() });
+            }
+        }
+
+        for node_id in node_ids {
+            let node = &self.nodes[node_id];
+            let incoming: Vec<&String> =
+                self.edges.iter().filter(|e| &e.to == node_id).map(|e| &e.from).collect();
+            if let Some(dependency) = incoming.iter().find(|from| !node.dependencies.contains(**from)) {
+                return Err(GraphError::UndeclaredDependency {
+                    node_id: node_id.clone(),
+                    dependency: dependency.to_string(),
+                });
+            }
+            if let Some(dependency) = node.dependencies.iter().find(|dep| !incoming.contains(dep)) {
+                return Err(GraphError::MissingDependencyEdge {
+                    node_id: node_id.clone(),
+                    dependency: dependency.clone(),
+                });
+            }
+            // Nodes without incoming edges take their inputs from the run's seed.
+            if incoming.is_empty() {
+                continue;
+            }
+            let provided: HashSet<&String> = incoming.iter().flat_map(|from| &self.nodes[*from].outputs).collect();
+            if let Some(input) = node.inputs.iter().find(|input| !provided.contains(input)) {
+                return Err(GraphError::MissingInput { node_id: node_id.clone(), input: input.clone() });
+            }
+        }
+        info!("[ericadamsai] Graph validation passed");
+        Ok(())
+    }
+
+    /// Make node declarations and edges agree
+    ///
+    /// Adds an unconditional edge for every declared dependency that has
+    /// none, then rewrites each node's `dependencies` from the edges.
+    pub fn reconcile(&mut self) -> Result<(), GraphError> {
+        let mut declared = Vec::new();
+        let mut node_ids: Vec<String> = self.nodes.keys().cloned().collect();
+        node_ids.sort();
+        for node_id in &node_ids {
+            let node = &self.nodes[node_id];
+            declared.extend(node.dependencies.iter().map(|dep| (dep.clone(), node_id.clone())));
+        }
+        let mut missing: Vec<GraphEdge> = Vec::new();
+        for (from, to) in declared {
+            let exists = self.edges.iter().chain(&missing).any(|e| e.from == from && e.to == to);
+            if !exists {
+                let edge = GraphEdge { from, to, condition: None };
+                self.check_edge(&edge)?;
+                missing.push(edge);
+            }
+        }
+        self.edges.extend(missing);
+        self.execution_order.clear();
+
+        for node in self.nodes.values_mut() {
+            node.dependencies.clear();
+        }
+        for edge in &self.edges {
+            if let Some(node) = self.nodes.get_mut(&edge.to) {
+                node.dependencies.push(edge.from.clone());
+            }
+        }
+        debug!("[ericadamsai] Graph reconciled: {}", self.id);
+        Ok(())
+    }
+
+    /// Check that an edge joins two distinct existing nodes and that its
+    /// condition parses
+    fn check_edge(&self, edge: &GraphEdge) -> Result<(), GraphError> {
+        for endpoint in [&edge.from, &edge.to] {
+            if !self.nodes.contains_key(endpoint) {
+                return Err(GraphError::DanglingEdge {
+                    from: edge.from.clone(),
+                    to: edge.to.clone(),
+                    missing: endpoint.clone(),
+                });
+            }
+        }
+        if edge.from == edge.to {
+            return Err(GraphError::SelfLoop { node_id: edge.from.clone() });
+        }
+        if let Some(condition) = &edge.condition {
+            Condition::parse(condition).map_err(|error| GraphError::InvalidCondition {
+                from: edge.from.clone(),
+                to: edge.to.clone(),
+                error,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Task node with no dependencies, inputs, outputs or metadata, for tests
+/// of the graph modules
+#[cfg(test)]
+pub(crate) fn task_node(id: &str) -> GraphNode {
+    GraphNode {
+        id: id.to_string(),
+        name: id.to_string(),
+        node_type: NodeType::Task,
+        dependencies: vec![],
+        outputs: vec![],
+        inputs: vec![],
+        metadata: serde_json::json!({}),
+    }
+}
+
+/// Unconditional edge, for tests of the graph modules
+#[cfg(test)]
+pub(crate) fn plain_edge(from: &str, to: &str) -> GraphEdge {
+    GraphEdge { from: from.to_string(), to: to.to_string(), condition: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_creation() {
+        let graph = ExecutionGraph::new("test-graph".to_string());
+        assert_eq!(graph.id, "test-graph");
+        assert_eq!(graph.nodes.len(), 0);
+    }
+
+    #[test]
+    fn test_add_node() {
+        let mut graph = ExecutionGraph::new("test-graph".to_string());
+        let node = GraphNode {
+            id: "node-1".to_string(),
+            name: "Test Node".to_string(),
+            node_type: NodeType::Task,
+            dependencies: vec![],
+            outputs: vec![],
+            inputs: vec![],
+            metadata: serde_json::json!({}),
+        };
+        assert!(graph.add_node(node).is_ok());
+    }
+
+    fn task(id: &str, dependencies: &[&str]) -> GraphNode {
+        let dependencies = dependencies.iter().map(|d| d.to_string()).collect();
+        GraphNode { dependencies, ..task_node(id) }
+    }
+
+    #[test]
+    fn test_validate_reports_structural_errors() {
+        let mut graph = ExecutionGraph::new("test-graph".to_string());
+        graph.add_node(task("fetch", &[])).unwrap();
+        graph.add_node(task("parse", &["fetch"])).unwrap();
+        graph.add_node(task("store", &["parse"])).unwrap();
+
+        assert_eq!(
+            graph.add_edge(plain_edge("parse", "parse")),
+            Err(GraphError::SelfLoop { node_id: "parse".to_string() })
+        );
+        assert_eq!(
+            graph.add_edge(plain_edge("parse", "missing")),
+            Err(GraphError::DanglingEdge {
+                from: "parse".to_string(),
+                to: "missing".to_string(),
+                missing: "missing".to_string(),
+            })
+        );
+
+        graph.add_edge(plain_edge("fetch", "parse")).unwrap();
+        assert_eq!(
+            graph.add_edge(plain_edge("fetch", "parse")),
+            Err(GraphError::DuplicateEdge { from: "fetch".to_string(), to: "parse".to_string() })
+        );
+        assert_eq!(
+            graph.validate(),
+            Err(GraphError::MissingDependencyEdge {
+                node_id: "store".to_string(),
+                dependency: "parse".to_string(),
+            })
+        );
+
+        graph.add_edge(plain_edge("parse", "store")).unwrap();
+        graph.edges.push(plain_edge("fetch", "store"));
+        assert_eq!(
+            graph.validate(),
+            Err(GraphError::UndeclaredDependency {
+                node_id: "store".to_string(),
+                dependency: "fetch".to_string(),
+            })
+        );
+
+        graph.nodes.get_mut("store").unwrap().dependencies.push("fetch".to_string());
+        graph.nodes.get_mut("store").unwrap().inputs = vec!["rows".to_string(), "status".to_string()];
+        graph.nodes.get_mut("parse").unwrap().outputs.push("rows".to_string());
+        assert_eq!(
+            graph.validate(),
+            Err(GraphError::MissingInput { node_id: "store".to_string(), input: "status".to_string() })
+        );
+        // A join may take each input from a different parent.
+        graph.nodes.get_mut("fetch").unwrap().outputs.push("status".to_string());
+        assert!(graph.validate().is_ok());
+
+        graph.edges.push(plain_edge("fetch", "parse"));
+        assert_eq!(
+            graph.validate(),
+            Err(GraphError::DuplicateEdge { from: "fetch".to_string(), to: "parse".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_reconcile_derives_edges_and_declarations() {
+        let mut graph = ExecutionGraph::new("test-graph".to_string());
+        graph.add_node(task("fetch", &[])).unwrap();
+        graph.add_node(task("parse", &["fetch"])).unwrap();
+        graph.add_node(task("store", &[])).unwrap();
+        graph.add_edge(plain_edge("parse", "store")).unwrap();
+        assert_eq!(graph.nodes["store"].dependencies, vec!["parse"]);
+        graph.nodes.get_mut("store").unwrap().dependencies.clear();
+
+        graph.reconcile().unwrap();
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(graph.nodes["parse"].dependencies, vec!["fetch"]);
+        assert_eq!(graph.nodes["store"].dependencies, vec!["parse"]);
+        assert!(graph.validate().is_ok());
+
+        graph.nodes.get_mut("store").unwrap().dependencies.push("unknown".to_string());
+        let error = graph.reconcile().unwrap_err();
+        assert_eq!(error.node_ids(), vec!["unknown", "store"]);
+        assert_eq!(graph.edges.len(), 2);
+    }
+
+    #[test]
+    fn test_deterministic_order_waves_and_cycles() {
+        let mut graph = ExecutionGraph::new("test-graph".to_string());
+        for id in ["d", "b", "c", "a", "e"] {
+            graph.add_node(task(id, &[])).unwrap();
+        }
+        graph.nodes.get_mut("c").unwrap().metadata = serde_json::json!({"priority": 5});
+        for (from, to) in [("a", "d"), ("b", "d"), ("c", "d"), ("d", "e")] {
+            graph.add_edge(plain_edge(from, to)).unwrap();
+        }
+
+        for _ in 0..5 {
+            graph.topological_sort().unwrap();
+            assert_eq!(graph.get_execution_order(), vec!["c", "a", "b", "d", "e"]);
+        }
+        assert_eq!(
+            graph.waves().unwrap(),
+            vec![vec!["c", "a", "b"], vec!["d"], vec!["e"]]
+        );
+
+        graph.add_node(task("f", &[])).unwrap();
+        graph.add_edge(plain_edge("e", "f")).unwrap();
+        graph.add_edge(plain_edge("f", "d")).unwrap();
+        let cycle = GraphError::Cycle { nodes: vec!["d".to_string(), "e".to_string(), "f".to_string()] };
+        assert_eq!(graph.topological_sort(), Err(cycle.clone()));
+        assert_eq!(graph.waves(), Err(cycle.clone()));
+        assert_eq!(cycle.to_string(), "cycle detected: d -> e -> f -> d");
+    }
+}
@@ -0,0 +1,197 @@
+//! Node Semantics - ericadamsai watermark
+//! Configuration and behavior of Decision, Loop and Aggregator nodes
+//!
+//! - `Task` nodes run through the graph's node executor.
+//! - `Decision` nodes run through the node executor, then take exactly one
+//!   outgoing edge: the first whose condition holds, or else the first edge
+//!   without a condition.
+//! - `Loop` nodes run the nested graph in `metadata.loop.body` repeatedly
+//!   until `metadata.loop.until` holds for its output or
+//!   `metadata.loop.max_iterations` is reached. Keeping the body as a nested
+//!   graph leaves the outer graph acyclic, and the cap bounds the unrolling.
+//! - `Aggregator` nodes merge the outputs of their upstream nodes with the
+//!   strategy in `metadata.strategy` without calling the node executor.
+//! - `Subgraph` nodes run the nested graph in `metadata.subgraph` once; see
+//!   [`SubgraphConfig`].
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Condition, ConditionError, ExecutionGraph, GraphError, GraphNode, NodeType, SubgraphConfig};
+
+/// Iteration cap used when a loop does not set one
+pub const DEFAULT_MAX_ITERATIONS: u32 = 10;
+
+/// Configuration of a `Loop` node, stored under `metadata.loop`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoopConfig {
+    /// Graph run once per iteration
+    pub body: ExecutionGraph,
+    /// Condition on the body's output that ends the loop; without one the
+    /// loop always runs `max_iterations` times
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default = "default_max_iterations")]
+    pub max_iterations: u32,
+}
+
+fn default_max_iterations() -> u32 {
+    DEFAULT_MAX_ITERATIONS
+}
+
+impl LoopConfig {
+    /// Read the loop configuration from a node's metadata
+    pub fn from_node(node: &GraphNode) -> Result<Self, GraphError> {
+        let invalid = |message: String| GraphError::InvalidNodeConfig { node_id: node.id.clone(), message };
+        let config = node
+            .metadata
+            .get("loop")
+            .ok_or_else(|| invalid("no `loop` metadata".to_string()))?;
+        let config: Self = serde_json::from_value(config.clone())
+            .map_err(|e| invalid(format!("invalid `loop` metadata: {}", e)))?;
+        if config.max_iterations == 0 {
+            return Err(invalid("a loop must allow at least one iteration".to_string()));
+        }
+        Ok(config)
+    }
+
+    /// Parsed `until` condition
+    pub fn until_condition(&self) -> Result<Option<Condition>, ConditionError> {
+        self.until.as_deref().map(Condition::parse).transpose()
+    }
+
+    /// Output of one iteration: the output of the body's only sink node, or
+    /// an object keyed by sink id if it has several
+    pub fn body_output(&self, outputs: &HashMap<String, Value>) -> Value {
+        self.body.sink_output(outputs)
+    }
+}
+
+/// How an `Aggregator` node merges its inputs, stored under `metadata.strategy`
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateStrategy {
+    /// Array of all inputs, with array inputs flattened one level
+    #[default]
+    Concat,
+    /// Most common input value, ties going to the earliest
+    MajorityVote,
+    /// Input of the upstream node that completed first
+    FirstSuccess,
+}
+
+impl AggregateStrategy {
+    /// Read the strategy from a node's metadata, defaulting to `Concat`
+    pub fn from_node(node: &GraphNode) -> Result<Self, GraphError> {
+        match node.metadata.get("strategy") {
+            None => Ok(Self::default()),
+            Some(strategy) => serde_json::from_value(strategy.clone()).map_err(|e| {
+                GraphError::InvalidNodeConfig {
+                    node_id: node.id.clone(),
+                    message: format!("invalid aggregate strategy: {}", e),
+                }
+            }),
+        }
+    }
+
+    /// Merge inputs given in incoming edge order, or in completion order for
+    /// `FirstSuccess`
+    pub fn aggregate(&self, inputs: Vec<Value>) -> Value {
+        match self {
+            AggregateStrategy::Concat => {
+                let mut merged = Vec::new();
+                for input in inputs {
+                    match input {
+                        Value::Array(items) => merged.extend(items),
+                        other => merged.push(other),
+                    }
+                }
+                Value::Array(merged)
+            }
+            AggregateStrategy::MajorityVote => {
+                let mut votes: Vec<(Value, usize)> = Vec::new();
+                for input in inputs {
+                    match votes.iter_mut().find(|(value, _)| *value == input) {
+                        Some((_, count)) => *count += 1,
+                        None => votes.push((input, 1)),
+                    }
+                }
+                // `max_by_key` keeps the last maximum, so search from the back.
+                votes
+                    .into_iter()
+                    .rev()
+                    .max_by_key(|(_, count)| *count)
+                    .map_or(Value::Null, |(value, _)| value)
+            }
+            AggregateStrategy::FirstSuccess => inputs.into_iter().next().unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Check the type-specific configuration of a node
+pub fn validate_node(node: &GraphNode) -> Result<(), GraphError> {
+    match node.node_type {
+        NodeType::Task | NodeType::Decision => Ok(()),
+        NodeType::Aggregator => AggregateStrategy::from_node(node).map(|_| ()),
+        NodeType::Subgraph => SubgraphConfig::from_node(node)?.validate(&node.id),
+        NodeType::Loop => {
+            let config = LoopConfig::from_node(node)?;
+            config.until_condition().map_err(|e| GraphError::InvalidNodeConfig {
+                node_id: node.id.clone(),
+                message: format!("`until` is an {}", e),
+            })?;
+            config.body.validate().map_err(|error| GraphError::InvalidSubgraph {
+                node_id: node.id.clone(),
+                error: Box::new(error),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_aggregate_strategies() {
+        let inputs = vec![json!(["a"]), json!("b"), json!("b"), json!(["a", "c"])];
+        assert_eq!(AggregateStrategy::Concat.aggregate(inputs.clone()), json!(["a", "b", "b", "a", "c"]));
+        assert_eq!(AggregateStrategy::MajorityVote.aggregate(inputs.clone()), json!("b"));
+        assert_eq!(AggregateStrategy::FirstSuccess.aggregate(inputs), json!(["a"]));
+        assert_eq!(
+            AggregateStrategy::MajorityVote.aggregate(vec![json!("x"), json!("y")]),
+            json!("x")
+        );
+        assert_eq!(AggregateStrategy::FirstSuccess.aggregate(vec![]), Value::Null);
+    }
+
+    #[test]
+    fn test_validate_loop_config() {
+        let mut node = GraphNode {
+            id: "retry-loop".to_string(),
+            name: "Retry".to_string(),
+            node_type: NodeType::Loop,
+            dependencies: vec![],
+            outputs: vec![],
+            inputs: vec![],
+            metadata: json!({}),
+        };
+        assert_eq!(
+            validate_node(&node),
+            Err(GraphError::InvalidNodeConfig {
+                node_id: "retry-loop".to_string(),
+                message: "no `loop` metadata".to_string(),
+            })
+        );
+
+        let body = ExecutionGraph::new("body".to_string());
+        node.metadata = json!({"loop": {"body": body, "until": "done =="}});
+        assert!(validate_node(&node).unwrap_err().to_string().contains("`until` is an invalid condition"));
+
+        node.metadata = json!({"loop": {"body": body, "until": "done", "max_iterations": 3}});
+        assert!(validate_node(&node).is_ok());
+        assert_eq!(LoopConfig::from_node(&node).unwrap().max_iterations, 3);
+    }
+}
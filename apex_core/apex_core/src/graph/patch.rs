@@ -0,0 +1,226 @@
+//! Graph Mutation - ericadamsai watermark
+//! Removing, replacing and renaming nodes and edges, and transactional patches
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug};
+
+use super::{ExecutionGraph, GraphEdge, GraphError, GraphNode};
+
+/// A single change to an execution graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum GraphPatch {
+    AddNode(GraphNode),
+    RemoveNode { node_id: String },
+    ReplaceNode(GraphNode),
+    RenameNode { from: String, to: String },
+    AddEdge(GraphEdge),
+    RemoveEdge { from: String, to: String },
+}
+
+impl ExecutionGraph {
+    /// Remove a node together with every edge into or out of it
+    pub fn remove_node(&mut self, node_id: &str) -> Result<GraphNode, GraphError> {
+        let node = self
+            .nodes
+            .remove(node_id)
+            .ok_or_else(|| GraphError::NodeNotFound { node_id: node_id.to_string() })?;
+        self.edges.retain(|edge| edge.from != node_id && edge.to != node_id);
+        for other in self.nodes.values_mut() {
+            other.dependencies.retain(|id| id != node_id);
+        }
+        self.execution_order.clear();
+        debug!("[ericadamsai] Removed node from graph: {}", node_id);
+        Ok(node)
+    }
+
+    /// Remove the edge between two nodes
+    pub fn remove_edge(&mut self, from: &str, to: &str) -> Result<GraphEdge, GraphError> {
+        let index = self
+            .edges
+            .iter()
+            .position(|edge| edge.from == from && edge.to == to)
+            .ok_or_else(|| GraphError::EdgeNotFound { from: from.to_string(), to: to.to_string() })?;
+        let edge = self.edges.remove(index);
+        if let Some(node) = self.nodes.get_mut(to) {
+            node.dependencies.retain(|id| id != from);
+        }
+        self.execution_order.clear();
+        debug!("[ericadamsai] Removed edge: {} -> {}", from, to);
+        Ok(edge)
+    }
+
+    /// Replace a node with a new definition of the same id, keeping its edges
+    ///
+    /// The node keeps the `dependencies` its incoming edges imply; those of
+    /// the new definition are ignored.
+    pub fn replace_node(&mut self, mut node: GraphNode) -> Result<GraphNode, GraphError> {
+        let slot = self
+            .nodes
+            .get_mut(&node.id)
+            .ok_or_else(|| GraphError::NodeNotFound { node_id: node.id.clone() })?;
+        node.dependencies = slot.dependencies.clone();
+        let previous = std::mem::replace(slot, node);
+        self.execution_order.clear();
+        debug!("[ericadamsai] Replaced node: {}", previous.id);
+        Ok(previous)
+    }
+
+    /// Change a node's id everywhere it is referenced
+    pub fn rename_node(&mut self, from: &str, to: &str) -> Result<(), GraphError> {
+        if !self.nodes.contains_key(from) {
+            return Err(GraphError::NodeNotFound { node_id: from.to_string() });
+        }
+        if from == to {
+            return Ok(());
+        }
+        if self.nodes.contains_key(to) {
+            return Err(GraphError::DuplicateNode { node_id: to.to_string() });
+        }
+        let mut node = self.nodes.remove(from).unwrap();
+        node.id = to.to_string();
+        self.nodes.insert(to.to_string(), node);
+
+        let rename = |id: &mut String| {
+            if id == from {
+                *id = to.to_string();
+            }
+        };
+        for edge in &mut self.edges {
+            rename(&mut edge.from);
+            rename(&mut edge.to);
+        }
+        for node in self.nodes.values_mut() {
+            node.dependencies.iter_mut().for_each(rename);
+        }
+        self.execution_order.clear();
+        debug!("[ericadamsai] Renamed node: {} -> {}", from, to);
+        Ok(())
+    }
+
+    /// Apply a single patch
+    pub fn apply_patch(&mut self, patch: GraphPatch) -> Result<(), GraphError> {
+        match patch {
+            GraphPatch::AddNode(node) => self.add_node(node),
+            GraphPatch::RemoveNode { node_id } => self.remove_node(&node_id).map(|_| ()),
+            GraphPatch::ReplaceNode(node) => self.replace_node(node).map(|_| ()),
+            GraphPatch::RenameNode { from, to } => self.rename_node(&from, &to),
+            GraphPatch::AddEdge(edge) => self.add_edge(edge),
+            GraphPatch::RemoveEdge { from, to } => self.remove_edge(&from, &to).map(|_| ()),
+        }
+    }
+
+    /// Apply a batch of patches as one transaction
+    ///
+    /// The patches are applied in order to a copy of the graph. If any patch
+    /// fails, or the result fails validation or contains a cycle, the graph
+    /// is left unchanged.
+    pub fn apply_patches(&mut self, patches: Vec<GraphPatch>) -> Result<(), GraphError> {
+        let count = patches.len();
+        let mut candidate = self.clone();
+        for (index, patch) in patches.into_iter().enumerate() {
+            candidate
+                .apply_patch(patch)
+                .map_err(|error| GraphError::Patch { index, error: Box::new(error) })?;
+        }
+        candidate.validate()?;
+        candidate.waves()?;
+
+        *self = candidate;
+        info!("[ericadamsai] Applied {} patches to graph {}", count, self.id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{plain_edge, task_node};
+
+    fn pipeline() -> ExecutionGraph {
+        let mut graph = ExecutionGraph::new("pipeline".to_string());
+        for id in ["fetch", "parse", "store"] {
+            graph.add_node(task_node(id)).unwrap();
+        }
+        graph.add_edge(plain_edge("fetch", "parse")).unwrap();
+        graph.add_edge(plain_edge("parse", "store")).unwrap();
+        graph.reconcile().unwrap();
+        graph.topological_sort().unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_mutations_keep_graph_consistent() {
+        let mut graph = pipeline();
+        assert_eq!(
+            graph.rename_node("missing", "store").unwrap_err(),
+            GraphError::NodeNotFound { node_id: "missing".to_string() }
+        );
+        assert_eq!(
+            graph.rename_node("parse", "store").unwrap_err(),
+            GraphError::DuplicateNode { node_id: "store".to_string() }
+        );
+        graph.rename_node("parse", "parse").unwrap();
+        assert_eq!(graph.execution_order.len(), 3);
+
+        graph.rename_node("parse", "transform").unwrap();
+        assert!(graph.execution_order.is_empty());
+        assert_eq!(graph.nodes["store"].dependencies, vec!["transform"]);
+        assert!(graph.validate().is_ok());
+
+        let mut store = task_node("store");
+        store.name = "Store results".to_string();
+        assert_eq!(graph.replace_node(store).unwrap().name, "store");
+        assert_eq!(graph.nodes["store"].name, "Store results");
+        assert_eq!(graph.nodes["store"].dependencies, vec!["transform"]);
+        assert!(graph.validate().is_ok());
+        graph.apply_patches(vec![GraphPatch::ReplaceNode(task_node("store"))]).unwrap();
+        assert_eq!(graph.nodes["store"].name, "store");
+
+        graph.remove_edge("transform", "store").unwrap();
+        assert!(graph.nodes["store"].dependencies.is_empty());
+        assert_eq!(
+            graph.remove_edge("transform", "store").unwrap_err(),
+            GraphError::EdgeNotFound { from: "transform".to_string(), to: "store".to_string() }
+        );
+
+        graph.remove_node("transform").unwrap();
+        assert!(graph.edges.is_empty());
+        assert!(graph.validate().is_ok());
+    }
+
+    #[test]
+    fn test_failed_patch_batch_rolls_back() {
+        let mut graph = pipeline();
+        let before = graph.execution_order.clone();
+
+        let error = graph
+            .apply_patches(vec![
+                GraphPatch::AddNode(task_node("notify")),
+                GraphPatch::AddEdge(plain_edge("store", "notify")),
+                GraphPatch::AddEdge(plain_edge("notify", "fetch")),
+            ])
+            .unwrap_err();
+        assert!(matches!(error, GraphError::Cycle { .. }));
+        assert!(!graph.nodes.contains_key("notify"));
+        assert_eq!(graph.execution_order, before);
+
+        let error = graph
+            .apply_patches(vec![
+                GraphPatch::RemoveNode { node_id: "parse".to_string() },
+                GraphPatch::RemoveEdge { from: "parse".to_string(), to: "store".to_string() },
+            ])
+            .unwrap_err();
+        assert!(matches!(error, GraphError::Patch { index: 1, .. }));
+        assert!(graph.nodes.contains_key("parse"));
+
+        let patches: Vec<GraphPatch> = serde_json::from_value(serde_json::json!([
+            {"op": "remove_node", "node_id": "store"},
+            {"op": "rename_node", "from": "parse", "to": "transform"},
+        ]))
+        .unwrap();
+        graph.apply_patches(patches).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.execution_order.is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+//! Schedule Estimation - ericadamsai watermark
+//! Critical path, slack and makespan from per-node duration and cost estimates
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use super::{ExecutionGraph, GraphError, GraphNode};
+
+/// Estimated duration and cost of a node, read from
+/// `metadata.estimated_duration_ms` and `metadata.estimated_cost`
+///
+/// A missing estimate counts as zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeEstimate {
+    pub duration_ms: u64,
+    pub cost: f64,
+}
+
+impl NodeEstimate {
+    /// Read a node's estimates from its metadata
+    pub fn from_node(node: &GraphNode) -> Result<Self, GraphError> {
+        let invalid = |key: &str, expected: &str| GraphError::InvalidNodeConfig {
+            node_id: node.id.clone(),
+            message: format!("`{}` must be {}", key, expected),
+        };
+        let duration_ms = match node.metadata.get("estimated_duration_ms") {
+            None | Some(Value::Null) => 0,
+            Some(value) => value
+                .as_u64()
+                .ok_or_else(|| invalid("estimated_duration_ms", "a whole number of milliseconds"))?,
+        };
+        let cost = match node.metadata.get("estimated_cost") {
+            None | Some(Value::Null) => 0.0,
+            Some(value) => value
+                .as_f64()
+                .filter(|cost| *cost >= 0.0)
+                .ok_or_else(|| invalid("estimated_cost", "a non-negative number"))?,
+        };
+        Ok(Self { duration_ms, cost })
+    }
+}
+
+/// Timing of a node when every node starts as soon as its dependencies
+/// finish, in milliseconds from the start of the run
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeTiming {
+    pub earliest_start: u64,
+    pub earliest_finish: u64,
+    /// Latest start that does not delay the run
+    pub latest_start: u64,
+    pub latest_finish: u64,
+    /// How long the node can be delayed without delaying the run
+    pub slack: u64,
+}
+
+/// Schedule estimate of a graph with unlimited concurrency
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScheduleEstimate {
+    pub timings: HashMap<String, NodeTiming>,
+    /// Longest chain of dependent nodes, from a root to a sink
+    pub critical_path: Vec<String>,
+    /// Length of the critical path
+    pub duration_ms: u64,
+    /// Sum of the estimated cost of every node
+    pub total_cost: f64,
+}
+
+impl ScheduleEstimate {
+    /// Whether delaying the node delays the whole run
+    pub fn is_critical(&self, node_id: &str) -> bool {
+        self.timings.get(node_id).is_some_and(|timing| timing.slack == 0)
+    }
+}
+
+impl ExecutionGraph {
+    /// Estimate node timings, slack and the critical path
+    ///
+    /// Every edge is assumed to be taken, so conditional branches count as
+    /// if they all ran.
+    pub fn estimate_schedule(&self) -> Result<ScheduleEstimate, GraphError> {
+        let estimates = self.estimates()?;
+        let order: Vec<String> = self.waves()?.concat();
+        let (_, successors) = self.adjacency();
+        let mut predecessors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (from, nexts) in &successors {
+            for next in nexts {
+                predecessors.entry(*next).or_default().push(*from);
+            }
+        }
+
+        let mut earliest_finish: HashMap<&str, u64> = HashMap::new();
+        for id in order.iter().map(String::as_str) {
+            let start = predecessors.get(id).into_iter().flatten().map(|from| earliest_finish[from]).max();
+            earliest_finish.insert(id, start.unwrap_or(0) + estimates[id].duration_ms);
+        }
+        let duration_ms = earliest_finish.values().copied().max().unwrap_or(0);
+
+        let mut latest_start: HashMap<&str, u64> = HashMap::new();
+        for id in order.iter().rev().map(String::as_str) {
+            let finish = successors[id].iter().map(|to| latest_start[to]).min().unwrap_or(duration_ms);
+            latest_start.insert(id, finish - estimates[id].duration_ms);
+        }
+
+        let timings: HashMap<String, NodeTiming> = order
+            .iter()
+            .map(|id| {
+                let id = id.as_str();
+                let duration = estimates[id].duration_ms;
+                let timing = NodeTiming {
+                    earliest_start: earliest_finish[id] - duration,
+                    earliest_finish: earliest_finish[id],
+                    latest_start: latest_start[id],
+                    latest_finish: latest_start[id] + duration,
+                    slack: latest_start[id] - (earliest_finish[id] - duration),
+                };
+                (id.to_string(), timing)
+            })
+            .collect();
+
+        // Follow zero-slack nodes that start exactly when the previous one
+        // finishes, preferring the smallest id where the path forks.
+        let mut critical_path: Vec<String> = Vec::new();
+        let mut next = order
+            .iter()
+            .map(String::as_str)
+            .filter(|id| timings[*id].slack == 0 && timings[*id].earliest_start == 0)
+            .min();
+        while let Some(id) = next {
+            critical_path.push(id.to_string());
+            let finish = timings[id].earliest_finish;
+            next = successors[id]
+                .iter()
+                .copied()
+                .filter(|to| timings[*to].slack == 0 && timings[*to].earliest_start == finish)
+                .min();
+        }
+
+        let total_cost = estimates.values().map(|estimate| estimate.cost).sum();
+        debug!("[ericadamsai] Estimated graph {}: {} ms, cost {}", self.id, duration_ms, total_cost);
+        Ok(ScheduleEstimate {
+            timings,
+            critical_path,
+            duration_ms,
+            total_cost,
+        })
+    }
+
+    /// Estimated run time when at most `concurrency` nodes run at once
+    ///
+    /// Simulates a scheduler that always starts the ready node with the
+    /// longest chain of work left after it, so nodes on the critical path
+    /// go first. A concurrency of zero is treated as one.
+    pub fn estimate_makespan(&self, concurrency: usize) -> Result<u64, GraphError> {
+        let schedule = self.estimate_schedule()?;
+        let estimates = self.estimates()?;
+        // Work left from a node's start to the end of the run on its longest chain
+        let rank = |id: &str| schedule.duration_ms - schedule.timings[id].latest_start;
+
+        let (mut in_degree, successors) = self.adjacency();
+        let mut ready: Vec<&str> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| *id)
+            .collect();
+        let mut running: Vec<(u64, &str)> = Vec::new();
+        let mut now = 0;
+        loop {
+            ready.sort_by_key(|id| (Reverse(rank(id)), *id));
+            let free = concurrency.max(1).saturating_sub(running.len()).min(ready.len());
+            for id in ready.drain(..free) {
+                running.push((now + estimates[id].duration_ms, id));
+            }
+            let Some(first) = (0..running.len()).min_by_key(|i| running[*i]) else {
+                break;
+            };
+            let (finish, id) = running.swap_remove(first);
+            now = finish;
+            for next in &successors[id] {
+                let degree = in_degree.get_mut(next).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(*next);
+                }
+            }
+        }
+        Ok(now)
+    }
+
+    /// Estimates of every node keyed by id
+    fn estimates(&self) -> Result<HashMap<&str, NodeEstimate>, GraphError> {
+        let mut node_ids: Vec<&String> = self.nodes.keys().collect();
+        node_ids.sort();
+        node_ids
+            .into_iter()
+            .map(|id| Ok((id.as_str(), NodeEstimate::from_node(&self.nodes[id])?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{plain_edge, task_node};
+    use serde_json::json;
+
+    fn graph() -> ExecutionGraph {
+        let mut graph = ExecutionGraph::new("estimate".to_string());
+        for (id, duration, cost) in [("a", 10, 1.0), ("b", 30, 2.0), ("c", 5, 0.5), ("d", 10, 1.0), ("e", 45, 0.5)] {
+            let metadata = json!({"estimated_duration_ms": duration, "estimated_cost": cost});
+            graph.add_node(GraphNode { metadata, ..task_node(id) }).unwrap();
+        }
+        for (from, to) in [("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")] {
+            graph.add_edge(plain_edge(from, to)).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_critical_path_and_slack() {
+        let schedule = graph().estimate_schedule().unwrap();
+        assert_eq!(schedule.duration_ms, 50);
+        assert_eq!(schedule.critical_path, vec!["a", "b", "d"]);
+        assert_eq!(schedule.total_cost, 5.0);
+        assert_eq!(
+            schedule.timings["c"],
+            NodeTiming { earliest_start: 10, earliest_finish: 15, latest_start: 35, latest_finish: 40, slack: 25 }
+        );
+        assert_eq!(schedule.timings["e"].slack, 5);
+        assert!(schedule.is_critical("b"));
+        assert!(!schedule.is_critical("e"));
+    }
+
+    #[test]
+    fn test_makespan_and_invalid_estimates() {
+        let mut graph = graph();
+        assert_eq!(graph.estimate_makespan(1).unwrap(), 100);
+        assert_eq!(graph.estimate_makespan(2).unwrap(), 55);
+        assert_eq!(graph.estimate_makespan(3).unwrap(), 50);
+        assert_eq!(ExecutionGraph::new("empty".to_string()).estimate_makespan(4).unwrap(), 0);
+
+        graph.nodes.get_mut("c").unwrap().metadata = json!({"estimated_duration_ms": "soon"});
+        assert!(matches!(
+            graph.estimate_schedule(),
+            Err(GraphError::InvalidNodeConfig { node_id, .. }) if node_id == "c"
+        ));
+    }
+}
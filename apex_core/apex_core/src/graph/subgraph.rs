@@ -0,0 +1,176 @@
+//! Subgraphs - ericadamsai watermark
+//! Embedding one execution graph as a single node of another
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use super::{ExecutionGraph, GraphError, GraphNode, GraphPatch, NodeInputs, NodeType};
+
+/// Configuration of a `Subgraph` node, stored under `metadata.subgraph`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubgraphConfig {
+    /// Graph run when the node runs
+    pub graph: ExecutionGraph,
+    /// Maps upstream node ids of the outer graph to the input keys the inner
+    /// graph's entry nodes see; when empty, inputs are passed through as is
+    #[serde(default)]
+    pub inputs: HashMap<String, String>,
+    /// Maps fields of the node's output to inner node ids; when empty, the
+    /// output is that of the inner graph's sink nodes
+    #[serde(default)]
+    pub outputs: HashMap<String, String>,
+}
+
+impl SubgraphConfig {
+    /// Embed a graph with inputs and outputs passed through as is
+    pub fn new(graph: ExecutionGraph) -> Self {
+        Self {
+            graph,
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+        }
+    }
+
+    /// Read the subgraph configuration from a node's metadata
+    pub fn from_node(node: &GraphNode) -> Result<Self, GraphError> {
+        let invalid = |message: String| GraphError::InvalidNodeConfig { node_id: node.id.clone(), message };
+        let config = node
+            .metadata
+            .get("subgraph")
+            .ok_or_else(|| invalid("no `subgraph` metadata".to_string()))?;
+        serde_json::from_value(config.clone()).map_err(|e| invalid(format!("invalid `subgraph` metadata: {}", e)))
+    }
+
+    /// Build a `Subgraph` node running this configuration
+    pub fn into_node(self, node_id: &str) -> GraphNode {
+        GraphNode {
+            id: node_id.to_string(),
+            name: self.graph.id.clone(),
+            node_type: NodeType::Subgraph,
+            dependencies: vec![],
+            outputs: vec![],
+            inputs: vec![],
+            metadata: serde_json::json!({ "subgraph": self }),
+        }
+    }
+
+    /// Check the inner graph and the output mapping
+    pub fn validate(&self, node_id: &str) -> Result<(), GraphError> {
+        self.graph.validate().map_err(|error| GraphError::InvalidSubgraph {
+            node_id: node_id.to_string(),
+            error: Box::new(error),
+        })?;
+        if let Some(inner) = self.outputs.values().find(|inner| !self.graph.nodes.contains_key(*inner)) {
+            return Err(GraphError::InvalidNodeConfig {
+                node_id: node_id.to_string(),
+                message: format!("output mapped from unknown inner node {}", inner),
+            });
+        }
+        Ok(())
+    }
+
+    /// Inputs for the inner graph's entry nodes
+    pub fn map_inputs(&self, inputs: NodeInputs) -> NodeInputs {
+        if self.inputs.is_empty() {
+            return inputs;
+        }
+        inputs
+            .into_iter()
+            .filter_map(|(from, output)| Some((self.inputs.get(&from)?.clone(), output)))
+            .collect()
+    }
+
+    /// Output of the node given the outputs of the inner nodes
+    pub fn map_outputs(&self, outputs: &HashMap<String, Value>) -> Value {
+        if self.outputs.is_empty() {
+            return self.graph.sink_output(outputs);
+        }
+        self.outputs
+            .iter()
+            .map(|(field, inner)| (field.clone(), outputs.get(inner).cloned().unwrap_or(Value::Null)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+impl ExecutionGraph {
+    /// Nodes without outgoing edges, sorted by id
+    pub fn sinks(&self) -> Vec<&String> {
+        let mut sinks: Vec<&String> = self
+            .nodes
+            .keys()
+            .filter(|id| !self.edges.iter().any(|edge| &edge.from == *id))
+            .collect();
+        sinks.sort();
+        sinks
+    }
+
+    /// Output of a run of this graph: the output of its only sink node, or
+    /// an object keyed by sink id if it has several
+    pub fn sink_output(&self, outputs: &HashMap<String, Value>) -> Value {
+        let sinks = self.sinks();
+        if let [sink] = sinks.as_slice() {
+            return outputs.get(*sink).cloned().unwrap_or(Value::Null);
+        }
+        sinks
+            .into_iter()
+            .filter_map(|id| Some((id.clone(), outputs.get(id)?.clone())))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Copy every node and edge of another graph into this one
+    ///
+    /// Fails without changing the graph if a node id is already taken.
+    pub fn merge(&mut self, other: ExecutionGraph) -> Result<(), GraphError> {
+        debug!("[ericadamsai] Merging graph {} into {}", other.id, self.id);
+        let mut node_ids: Vec<&String> = other.nodes.keys().collect();
+        node_ids.sort();
+        let mut patches: Vec<GraphPatch> = node_ids
+            .into_iter()
+            .map(|id| GraphPatch::AddNode(other.nodes[id].clone()))
+            .collect();
+        patches.extend(other.edges.iter().cloned().map(GraphPatch::AddEdge));
+        self.apply_patches(patches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::task_node;
+    use serde_json::json;
+
+    #[test]
+    fn test_subgraph_mapping() {
+        let mut inner = ExecutionGraph::new("scoring".to_string());
+        for id in ["embed", "score", "explain"] {
+            inner.add_node(task_node(id)).unwrap();
+        }
+        let mut config = SubgraphConfig::new(inner);
+        let outputs = HashMap::from([
+            ("score".to_string(), json!(0.9)),
+            ("explain".to_string(), json!("why")),
+        ]);
+        assert_eq!(
+            config.map_outputs(&outputs),
+            json!({"score": 0.9, "explain": "why"})
+        );
+
+        config.inputs.insert("fetch".to_string(), "document".to_string());
+        config.outputs.insert("result".to_string(), "score".to_string());
+        let inputs = HashMap::from([
+            ("fetch".to_string(), json!("text")),
+            ("other".to_string(), json!(1)),
+        ]);
+        assert_eq!(config.map_inputs(inputs), HashMap::from([("document".to_string(), json!("text"))]));
+        assert_eq!(config.map_outputs(&outputs), json!({"result": 0.9}));
+
+        let node = config.clone().into_node("scoring-step");
+        assert!(SubgraphConfig::from_node(&node).unwrap().validate(&node.id).is_ok());
+        config.outputs.insert("missing".to_string(), "nowhere".to_string());
+        assert!(config.validate("scoring-step").is_err());
+    }
+}
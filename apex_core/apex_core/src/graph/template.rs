@@ -0,0 +1,266 @@
+//! Graph Templates - ericadamsai watermark
+//! Parameterized graphs instantiated under namespaced node ids
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use super::{Condition, ExecutionGraph, GraphError, GraphNode, SubgraphConfig};
+
+/// Parameter of a graph template
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TemplateParameter {
+    pub name: String,
+    /// Value used when an instantiation does not set the parameter
+    #[serde(default)]
+    pub default: Option<Value>,
+}
+
+/// Reusable graph with `{{parameter}}` placeholders
+///
+/// Placeholders may appear in node names, node metadata and edge
+/// conditions. A string that is exactly one placeholder is replaced by the
+/// parameter value itself; placeholders inside longer strings are replaced
+/// by the value's text. In edge conditions a placeholder stands for a
+/// literal, as described in [`Condition::fill`]. The template graph is only
+/// validated once instantiated.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphTemplate {
+    pub id: String,
+    pub parameters: Vec<TemplateParameter>,
+    pub graph: ExecutionGraph,
+}
+
+impl GraphTemplate {
+    /// Create a new template without parameters
+    pub fn new(id: String, graph: ExecutionGraph) -> Self {
+        Self {
+            id,
+            parameters: Vec::new(),
+            graph,
+        }
+    }
+
+    /// Add a parameter, optionally with a default value
+    pub fn parameter(mut self, name: &str, default: Option<Value>) -> Self {
+        self.parameters.push(TemplateParameter {
+            name: name.to_string(),
+            default,
+        });
+        self
+    }
+
+    /// Create a copy of the template graph with parameters filled in and
+    /// every node id prefixed with `namespace/`
+    pub fn instantiate(&self, namespace: &str, values: &HashMap<String, Value>) -> Result<ExecutionGraph, GraphError> {
+        let values = self.resolve(values)?;
+        let rename = |id: &String| format!("{}/{}", namespace, id);
+        debug!("[ericadamsai] Instantiating template {} as {}", self.id, namespace);
+
+        let mut graph = ExecutionGraph::new(namespace.to_string());
+        for node in self.graph.nodes.values() {
+            let node = GraphNode {
+                id: rename(&node.id),
+                name: substitute_text(&node.name, &values),
+                node_type: node.node_type.clone(),
+                dependencies: node.dependencies.iter().map(rename).collect(),
+                outputs: node.outputs.clone(),
+                inputs: node.inputs.clone(),
+                metadata: substitute(&node.metadata, &values),
+            };
+            graph.nodes.insert(node.id.clone(), node);
+        }
+        for edge in &self.graph.edges {
+            let mut edge = edge.clone();
+            edge.from = rename(&edge.from);
+            edge.to = rename(&edge.to);
+            edge.condition = match &edge.condition {
+                Some(condition) => Some(
+                    Condition::fill(condition, &values)
+                        .map_err(|error| GraphError::InvalidCondition {
+                            from: edge.from.clone(),
+                            to: edge.to.clone(),
+                            error,
+                        })?
+                        .to_string(),
+                ),
+                None => None,
+            };
+            graph.edges.push(edge);
+        }
+        graph.validate()?;
+        Ok(graph)
+    }
+
+    /// Instantiate the template as a single `Subgraph` node
+    pub fn instantiate_node(&self, node_id: &str, values: &HashMap<String, Value>) -> Result<GraphNode, GraphError> {
+        let graph = self.instantiate(node_id, values)?;
+        Ok(SubgraphConfig::new(graph).into_node(node_id))
+    }
+
+    /// Parameter values with defaults applied
+    fn resolve(&self, values: &HashMap<String, Value>) -> Result<HashMap<String, Value>, GraphError> {
+        if let Some(name) = values.keys().find(|name| !self.parameters.iter().any(|p| &p.name == *name)) {
+            return Err(GraphError::UnknownParameter {
+                template: self.id.clone(),
+                parameter: name.clone(),
+            });
+        }
+        self.parameters
+            .iter()
+            .map(|parameter| {
+                let value = values
+                    .get(&parameter.name)
+                    .or(parameter.default.as_ref())
+                    .cloned()
+                    .ok_or_else(|| GraphError::MissingParameter {
+                        template: self.id.clone(),
+                        parameter: parameter.name.clone(),
+                    })?;
+                Ok((parameter.name.clone(), value))
+            })
+            .collect()
+    }
+}
+
+fn substitute(value: &Value, values: &HashMap<String, Value>) -> Value {
+    match value {
+        Value::String(text) => {
+            let name = text.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}"));
+            match name.and_then(|name| values.get(name.trim())) {
+                Some(value) => value.clone(),
+                None => Value::String(substitute_text(text, values)),
+            }
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| substitute(item, values)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), substitute(value, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Replace placeholders in one pass, so text from a value is never
+/// substituted again
+fn substitute_text(text: &str, values: &HashMap<String, Value>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end + 2) else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        match values.get(rest[start + 2..end - 2].trim()) {
+            Some(Value::String(s)) => result.push_str(s),
+            Some(other) => result.push_str(&other.to_string()),
+            None => result.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{task_node, ConditionContext, GraphEdge, NodeType};
+    use serde_json::json;
+
+    fn template() -> GraphTemplate {
+        let mut graph = ExecutionGraph::new("classify".to_string());
+        for (id, metadata) in [
+            ("fetch", json!({"url": "https://{{host}}/items"})),
+            ("score", json!({"model": "{{model}}", "threshold": "{{threshold}}"})),
+        ] {
+            let name = format!("{} ({{{{model}}}})", id);
+            graph.add_node(GraphNode { name, metadata, ..task_node(id) }).unwrap();
+        }
+        // Pushed directly since the condition only parses once filled in.
+        graph.edges.push(GraphEdge {
+            from: "fetch".to_string(),
+            to: "score".to_string(),
+            condition: Some("count > {{threshold}}".to_string()),
+        });
+        graph.nodes.get_mut("score").unwrap().dependencies.push("fetch".to_string());
+        GraphTemplate::new("classify".to_string(), graph)
+            .parameter("host", None)
+            .parameter("model", Some(json!("small")))
+            .parameter("threshold", Some(json!(3)))
+    }
+
+    #[test]
+    fn test_instantiate_twice_into_one_graph() {
+        let template = template();
+        let mut workflow = ExecutionGraph::new("workflow".to_string());
+        let values = HashMap::from([("host".to_string(), json!("a.example"))]);
+        workflow.merge(template.instantiate("east", &values).unwrap()).unwrap();
+        let values = HashMap::from([
+            ("host".to_string(), json!("b.example")),
+            ("model".to_string(), json!("large")),
+        ]);
+        workflow.merge(template.instantiate("west", &values).unwrap()).unwrap();
+
+        assert_eq!(workflow.nodes.len(), 4);
+        assert_eq!(workflow.nodes["east/fetch"].metadata["url"], json!("https://a.example/items"));
+        assert_eq!(workflow.nodes["west/score"].metadata, json!({"model": "large", "threshold": 3}));
+        assert_eq!(workflow.nodes["west/score"].name, "score (large)");
+        assert_eq!(workflow.edges[1].from, "west/fetch");
+        assert_eq!(workflow.edges[1].condition.as_deref(), Some("count > 3"));
+        assert!(matches!(
+            workflow.merge(template.instantiate("east", &values).unwrap()),
+            Err(GraphError::Patch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parameter_errors() {
+        let template = template();
+        assert_eq!(
+            template.instantiate("east", &HashMap::new()).unwrap_err(),
+            GraphError::MissingParameter { template: "classify".to_string(), parameter: "host".to_string() }
+        );
+        let values = HashMap::from([
+            ("host".to_string(), json!("a")),
+            ("colour".to_string(), json!("red")),
+        ]);
+        assert!(matches!(
+            template.instantiate("east", &values),
+            Err(GraphError::UnknownParameter { .. })
+        ));
+
+        let values = HashMap::from([("host".to_string(), json!("a"))]);
+        let node = template.instantiate_node("classify-a", &values).unwrap();
+        assert_eq!(node.node_type, NodeType::Subgraph);
+        let config = SubgraphConfig::from_node(&node).unwrap();
+        assert!(config.graph.nodes.contains_key("classify-a/fetch"));
+    }
+
+    #[test]
+    fn test_parameters_fill_conditions_as_literals() {
+        let mut template = template();
+        template.graph.edges[0].condition = Some("label == {{model}} && count > {{ threshold }}".to_string());
+        let values = HashMap::from([
+            ("host".to_string(), json!("{{model}}")),
+            ("model".to_string(), json!("0\" || true || \"")),
+            ("threshold".to_string(), json!(-2.5)),
+        ]);
+        let graph = template.instantiate("east", &values).unwrap();
+        let condition = graph.edges[0].condition.as_deref().unwrap();
+        assert_eq!(condition, r#"label == "0\" || true || \"" && count > -2.5"#);
+        let output = json!({"label": "small", "count": 9});
+        let context = ConditionContext { output: Some(&output), metadata: &Value::Null };
+        assert!(!Condition::parse(condition).unwrap().evaluate(&context).unwrap());
+        assert_eq!(graph.nodes["east/fetch"].metadata["url"], json!("https://{{model}}/items"));
+
+        let values = HashMap::from([("host".to_string(), json!("a")), ("model".to_string(), json!(["x"]))]);
+        assert!(matches!(
+            template.instantiate("east", &values),
+            Err(GraphError::InvalidCondition { .. })
+        ));
+    }
+}
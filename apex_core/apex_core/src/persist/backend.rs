@@ -0,0 +1,134 @@
+//! Storage Backends - ericadamsai watermark
+//! Key-value storage trait behind DataStore and the registry of backend factories
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use super::filesystem::FileSystemBackend;
+#[cfg(feature = "lmdb")]
+use super::lmdb::LmdbBackend;
+use super::memory::MemoryBackend;
+#[cfg(feature = "postgres")]
+use super::postgres::PostgresBackend;
+#[cfg(feature = "redis")]
+use super::redis::RedisBackend;
+#[cfg(feature = "s3")]
+use super::s3::S3Backend;
+use super::PersistenceConfig;
+
+/// Error produced by a storage backend
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("storage I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("no storage backend registered as {0}")]
+    UnknownBackend(String),
+    #[error("invalid storage configuration: {0}")]
+    InvalidConfig(String),
+    #[error("storage backend error: {0}")]
+    Backend(String),
+    #[error("write to {0} rejected: stored value changed")]
+    PreconditionFailed(String),
+    #[error("invalid storage key {0:?}")]
+    InvalidKey(String),
+}
+
+/// Key-value storage used by [`super::DataStore`]
+///
+/// Keys are `/`-separated paths such as `engines/main/state`; values are
+/// opaque bytes.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Value stored under a key, if any
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Store a value, replacing any previous value
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<(), StorageError>;
+
+    /// Store several values; backends may write them in one round trip
+    async fn put_many(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), StorageError> {
+        for (key, value) in entries {
+            self.put(&key, value).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a key; removing a missing key is not an error
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+
+    /// Keys starting with `prefix`, sorted
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError>;
+
+    /// Whether a value is stored under a key
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(self.get(key).await?.is_some())
+    }
+}
+
+/// Builds a backend from a persistence configuration
+pub type BackendFactory =
+    Arc<dyn Fn(&PersistenceConfig) -> Result<Box<dyn StorageBackend>, StorageError> + Send + Sync>;
+
+/// Storage backends by name, see [`super::PersistenceBackend::name`]
+///
+/// The default registry holds the built-in backends; register a factory
+/// under a new name to plug in another one, or under a built-in name to
+/// replace it.
+#[derive(Clone)]
+pub struct BackendRegistry {
+    factories: HashMap<String, BackendFactory>,
+}
+
+impl BackendRegistry {
+    /// Create a registry without any backend
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Register a backend factory under a name
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&PersistenceConfig) -> Result<Box<dyn StorageBackend>, StorageError> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Arc::new(factory));
+    }
+
+    /// Names of the registered backends, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// Build the backend a configuration selects
+    pub fn build(&self, config: &PersistenceConfig) -> Result<Box<dyn StorageBackend>, StorageError> {
+        let name = config.backend.name();
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| StorageError::UnknownBackend(name.to_string()))?;
+        factory(config)
+    }
+}
+
+impl Default for BackendRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register("filesystem", |config| {
+            Ok(Box::new(FileSystemBackend::new(&config.connection_string)))
+        });
+        registry.register("memory", |_| Ok(Box::new(MemoryBackend::new())));
+        #[cfg(feature = "lmdb")]
+        registry.register("lmdb", |config| Ok(Box::new(LmdbBackend::from_config(config)?)));
+        #[cfg(feature = "postgres")]
+        registry.register("postgresql", |config| Ok(Box::new(PostgresBackend::from_config(config)?)));
+        #[cfg(feature = "redis")]
+        registry.register("redis", |config| Ok(Box::new(RedisBackend::from_config(config)?)));
+        #[cfg(feature = "s3")]
+        registry.register("s3", |config| Ok(Box::new(S3Backend::from_config(config)?)));
+        registry
+    }
+}
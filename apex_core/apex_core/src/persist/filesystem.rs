@@ -0,0 +1,133 @@
+//! File System Backend - ericadamsai watermark
+//! Stores each key as a file under `<root>/.data/<key>.json`
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use async_trait::async_trait;
+use tokio::fs;
+use tracing::info;
+
+use super::backend::{StorageBackend, StorageError};
+
+const EXTENSION: &str = ".json";
+
+/// Storage backend writing one file per key
+#[derive(Clone, Debug)]
+pub struct FileSystemBackend {
+    root: PathBuf,
+}
+
+impl FileSystemBackend {
+    /// Store files under `<root>/.data`
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: PathBuf::from(root).join(".data"),
+        }
+    }
+
+    /// File of a key; keys must be relative paths without `.` or `..`
+    /// components, so they cannot point outside the root
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let valid = !key.contains('\\') && key.split('/').all(|part| !matches!(part, "" | "." | ".."));
+        if !valid {
+            return Err(StorageError::InvalidKey(key.to_string()));
+        }
+        Ok(self.root.join(format!("{}{}", key, EXTENSION)))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FileSystemBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, value).await?;
+        info!("[ericadamsai] Data saved to filesystem: {}", path.display());
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => {
+                info!("[ericadamsai] Data deleted: {}", path.display());
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let mut keys = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(&self.root) else {
+                    continue;
+                };
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                if let Some(key) = relative.strip_suffix(EXTENSION) {
+                    if key.starts_with(prefix) {
+                        keys.push(key.to_string());
+                    }
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(fs::try_exists(self.path(key)?).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_nested_keys() {
+        let dir = std::env::temp_dir().join(format!("apex-fs-backend-{}", uuid::Uuid::new_v4()));
+        let backend = FileSystemBackend::new(&dir.to_string_lossy());
+        assert_eq!(backend.list("").await.unwrap(), Vec::<String>::new());
+
+        for key in ["engines/a/state", "engines/b/state", "graph-runs/r1/checkpoint"] {
+            backend.put(key, b"{}".to_vec()).await.unwrap();
+        }
+        assert_eq!(backend.list("engines/").await.unwrap(), vec!["engines/a/state", "engines/b/state"]);
+        assert_eq!(backend.get("engines/a/state").await.unwrap(), Some(b"{}".to_vec()));
+        assert!(backend.exists("graph-runs/r1/checkpoint").await.unwrap());
+
+        backend.delete("engines/a/state").await.unwrap();
+        backend.delete("engines/a/state").await.unwrap();
+        assert_eq!(backend.get("engines/a/state").await.unwrap(), None);
+
+        for key in ["../escape", "tasks/../../escape", "/etc/passwd", "tasks/./a", "tasks//a", "a\\b", ""] {
+            assert!(matches!(backend.put(key, b"{}".to_vec()).await, Err(StorageError::InvalidKey(_))), "{}", key);
+        }
+        assert!(matches!(backend.get("..").await, Err(StorageError::InvalidKey(_))));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,220 @@
+//! LMDB Backend - ericadamsai watermark
+//! Embedded, transactional storage for single-node deployments via heed
+
+use std::path::PathBuf;
+use async_trait::async_trait;
+use heed::types::{Bytes, Str};
+use heed::{Database, Env, EnvOpenOptions, RwTxn};
+use tracing::{info, debug};
+
+use super::backend::{StorageBackend, StorageError};
+use super::PersistenceConfig;
+
+/// LMDB backend settings
+///
+/// Read from `PersistenceConfig.options` by [`LmdbConfig::from_config`]:
+/// `namespace`, `map_size_mb` and `max_dbs`.
+#[derive(Clone, Debug)]
+pub struct LmdbConfig {
+    /// Directory holding the environment; created if missing
+    pub path: PathBuf,
+    /// Named database holding this backend's keys
+    pub namespace: String,
+    /// Largest size the environment may grow to, in MiB
+    pub map_size_mb: usize,
+    /// Most named databases the environment may hold
+    pub max_dbs: u32,
+}
+
+impl LmdbConfig {
+    /// Settings for an environment directory with the default namespace `apex`
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            namespace: "apex".to_string(),
+            map_size_mb: 1024,
+            max_dbs: 16,
+        }
+    }
+
+    /// Settings from a persistence configuration, whose connection string
+    /// is the environment directory
+    pub fn from_config(config: &PersistenceConfig) -> Result<Self, StorageError> {
+        let mut lmdb = Self::new(&config.connection_string);
+        let invalid = |key: &str| StorageError::InvalidConfig(format!("lmdb option `{}` must be a number", key));
+        for (key, value) in &config.options {
+            match key.as_str() {
+                "namespace" => lmdb.namespace = value.clone(),
+                "map_size_mb" => lmdb.map_size_mb = value.parse().map_err(|_| invalid(key))?,
+                "max_dbs" => lmdb.max_dbs = value.parse().map_err(|_| invalid(key))?,
+                _ => return Err(StorageError::InvalidConfig(format!("unknown lmdb option `{}`", key))),
+            }
+        }
+        Ok(lmdb)
+    }
+}
+
+/// Storage backend on an LMDB environment
+///
+/// Each namespace is a named database of the environment, so several
+/// backends can share one directory. Every write is one transaction that
+/// is synced to disk on commit, so a crash never leaves a partial write.
+/// Writes run on the blocking thread pool; reads are served straight from
+/// the memory map, see [`LmdbBackend::read`].
+pub struct LmdbBackend {
+    config: LmdbConfig,
+    env: Env,
+    db: Database<Str, Bytes>,
+}
+
+impl LmdbBackend {
+    /// Open or create the environment and the namespace's database
+    pub fn new(config: LmdbConfig) -> Result<Self, StorageError> {
+        std::fs::create_dir_all(&config.path)?;
+        // SAFETY: the environment files are only modified through LMDB, which
+        // handles concurrent access from other processes with its lock file.
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(config.map_size_mb * 1024 * 1024)
+                .max_dbs(config.max_dbs)
+                .open(&config.path)
+        }
+        .map_err(lmdb_error)?;
+
+        let mut wtxn = env.write_txn().map_err(lmdb_error)?;
+        let db = env
+            .create_database(&mut wtxn, Some(&config.namespace))
+            .map_err(lmdb_error)?;
+        wtxn.commit().map_err(lmdb_error)?;
+        info!(
+            "[ericadamsai] LMDB backend for namespace {} at {}",
+            config.namespace,
+            config.path.display()
+        );
+        Ok(Self { config, env, db })
+    }
+
+    /// Open a backend from a persistence configuration
+    pub fn from_config(config: &PersistenceConfig) -> Result<Self, StorageError> {
+        Self::new(LmdbConfig::from_config(config)?)
+    }
+
+    /// Call `read` with the value stored under a key, borrowed from the
+    /// memory map without copying it
+    pub fn read<T>(&self, key: &str, read: impl FnOnce(Option<&[u8]>) -> T) -> Result<T, StorageError> {
+        let rtxn = self.env.read_txn().map_err(lmdb_error)?;
+        let value = self.db.get(&rtxn, key).map_err(lmdb_error)?;
+        Ok(read(value))
+    }
+
+    /// Run `write` in one write transaction on the blocking thread pool
+    async fn write<F>(&self, write: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut RwTxn<'_>, Database<Str, Bytes>) -> heed::Result<()> + Send + 'static,
+    {
+        let env = self.env.clone();
+        let db = self.db;
+        tokio::task::spawn_blocking(move || {
+            let mut wtxn = env.write_txn()?;
+            write(&mut wtxn, db)?;
+            wtxn.commit()
+        })
+        .await
+        .map_err(|e| StorageError::Backend(e.to_string()))?
+        .map_err(lmdb_error)
+    }
+}
+
+fn lmdb_error(error: heed::Error) -> StorageError {
+    match error {
+        heed::Error::Io(error) => StorageError::Io(error),
+        error => StorageError::Backend(error.to_string()),
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LmdbBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        self.read(key, |value| value.map(<[u8]>::to_vec))
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<(), StorageError> {
+        let key = key.to_string();
+        self.write(move |wtxn, db| db.put(wtxn, &key, &value)).await
+    }
+
+    /// Writes every value in one transaction
+    async fn put_many(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), StorageError> {
+        let count = entries.len();
+        self.write(move |wtxn, db| {
+            for (key, value) in &entries {
+                db.put(wtxn, key, value)?;
+            }
+            Ok(())
+        })
+        .await?;
+        debug!("[ericadamsai] Wrote {} values to {}", count, self.config.namespace);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let key = key.to_string();
+        self.write(move |wtxn, db| db.delete(wtxn, &key).map(|_| ())).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let rtxn = self.env.read_txn().map_err(lmdb_error)?;
+        let keys: heed::Result<Vec<String>> = if prefix.is_empty() {
+            // LMDB rejects empty keys, so an empty prefix cannot be looked up
+            self.db.iter(&rtxn).and_then(|iter| iter.map(|entry| Ok(entry?.0.to_string())).collect())
+        } else {
+            self.db
+                .prefix_iter(&rtxn, prefix)
+                .and_then(|iter| iter.map(|entry| Ok(entry?.0.to_string())).collect())
+        };
+        keys.map_err(lmdb_error)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        self.read(key, |value| value.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{DataStore, PersistenceBackend};
+
+    fn config(dir: &std::path::Path, namespace: &str) -> PersistenceConfig {
+        PersistenceConfig {
+            backend: PersistenceBackend::Lmdb,
+            connection_string: dir.to_string_lossy().to_string(),
+            cache_enabled: false,
+            compression: false,
+            options: [("namespace".to_string(), namespace.to_string())].into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_and_durability() {
+        let dir = std::env::temp_dir().join(format!("apex-lmdb-{}", uuid::Uuid::new_v4()));
+        let runs = LmdbBackend::from_config(&config(&dir, "runs")).unwrap();
+        let engines = LmdbBackend::from_config(&config(&dir, "engines")).unwrap();
+
+        runs.put_many(vec![("r/1".to_string(), b"one".to_vec()), ("r/2".to_string(), b"two".to_vec())])
+            .await
+            .unwrap();
+        engines.put("r/1", b"engine".to_vec()).await.unwrap();
+        assert_eq!(runs.list("r/").await.unwrap(), vec!["r/1", "r/2"]);
+        assert!(runs.read("r/1", |value| value == Some(&b"one"[..])).unwrap());
+        assert_eq!(engines.get("r/1").await.unwrap(), Some(b"engine".to_vec()));
+        assert!(!engines.exists("r/2").await.unwrap());
+
+        runs.delete("r/2").await.unwrap();
+        drop(runs);
+        let store = DataStore::new(config(&dir, "runs")).unwrap();
+        assert_eq!(store.list("").await.unwrap(), vec!["r/1"]);
+        drop((engines, store));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,64 @@
+//! Memory Backend - ericadamsai watermark
+//! In-process storage backend for tests and ephemeral stores
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+
+use super::backend::{StorageBackend, StorageError};
+
+/// Storage backend keeping every value in memory
+///
+/// Clones share the same entries.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    entries: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryBackend {
+    /// Create an empty memory backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored keys
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no key is stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl StorageBackend for MemoryBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<(), StorageError> {
+        self.entries.lock().unwrap().insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .range(prefix.to_string()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(self.entries.lock().unwrap().contains_key(key))
+    }
+}
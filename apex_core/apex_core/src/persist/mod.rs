@@ -0,0 +1,282 @@
+//! Persistence Module - ericadamsai watermark
+//! Handles data persistence, serialization, and storage operations
+
+pub mod backend;
+pub mod filesystem;
+#[cfg(feature = "lmdb")]
+pub mod lmdb;
+pub mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "s3")]
+pub mod s3;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug};
+use std::collections::HashMap;
+
+pub use backend::{BackendFactory, BackendRegistry, StorageBackend, StorageError};
+pub use filesystem::FileSystemBackend;
+#[cfg(feature = "lmdb")]
+pub use lmdb::{LmdbBackend, LmdbConfig};
+pub use memory::MemoryBackend;
+#[cfg(feature = "postgres")]
+pub use postgres::{PostgresBackend, PostgresConfig};
+#[cfg(feature = "redis")]
+pub use self::redis::{RedisBackend, RedisConfig};
+#[cfg(feature = "s3")]
+pub use s3::{S3Backend, S3Config, WriteCondition};
+
+/// Persistence backend type
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PersistenceBackend {
+    FileSystem,
+    Redis,
+    PostgreSQL,
+    S3,
+    Memory,
+    Lmdb,
+    /// Backend registered in a [`BackendRegistry`] under this name
+    Custom(String),
+}
+
+impl PersistenceBackend {
+    /// Name the backend is registered under in a [`BackendRegistry`]
+    pub fn name(&self) -> &str {
+        match self {
+            PersistenceBackend::FileSystem => "filesystem",
+            PersistenceBackend::Redis => "redis",
+            PersistenceBackend::PostgreSQL => "postgresql",
+            PersistenceBackend::S3 => "s3",
+            PersistenceBackend::Memory => "memory",
+            PersistenceBackend::Lmdb => "lmdb",
+            PersistenceBackend::Custom(name) => name,
+        }
+    }
+}
+
+/// Persistence configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistenceConfig {
+    pub backend: PersistenceBackend,
+    pub connection_string: String,
+    pub cache_enabled: bool,
+    pub compression: bool,
+    /// Backend-specific settings; see each backend's configuration
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+}
+
+/// Persistent data store
+pub struct DataStore {
+    config: PersistenceConfig,
+    backend: Box<dyn StorageBackend>,
+    cache: std::sync::Arc<std::sync::Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl DataStore {
+    /// Create a new data store with one of the built-in backends
+    pub fn new(config: PersistenceConfig) -> Result<Self, String> {
+        Self::with_registry(config, &BackendRegistry::default())
+    }
+
+    /// Create a new data store with the backend a registry builds for the
+    /// configuration
+    pub fn with_registry(config: PersistenceConfig, registry: &BackendRegistry) -> Result<Self, String> {
+        let backend = registry.build(&config).map_err(|e| e.to_string())?;
+        Ok(Self::with_backend(config, backend))
+    }
+
+    /// Create a new data store on an already built backend
+    pub fn with_backend(config: PersistenceConfig, backend: Box<dyn StorageBackend>) -> Self {
+        info!("[ericadamsai] Initializing DataStore with {:?} backend", config.backend);
+        Self {
+            config,
+            backend,
+            cache: std::sync::Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Save data to persistence layer
+    pub async fn save<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        debug!("[ericadamsai] Saving data with key: {}", key);
+
+        let serialized = serde_json::to_vec(value)
+            .map_err(|e| format!("Serialization error: {}", e))?;
+
+        // Update cache if enabled
+        if self.config.cache_enabled {
+            let mut cache = self.cache.lock().unwrap();
+            cache.insert(key.to_string(), serialized.clone());
+        }
+
+        self.backend.put(key, serialized).await.map_err(|e| e.to_string())
+    }
+
+    /// Save several values at once
+    ///
+    /// Backends that support it write every value in one round trip.
+    pub async fn save_many<T: Serialize>(&self, entries: &[(&str, T)]) -> Result<(), String> {
+        debug!("[ericadamsai] Saving {} values", entries.len());
+
+        let mut serialized = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let data = serde_json::to_vec(value)
+                .map_err(|e| format!("Serialization error: {}", e))?;
+            serialized.push((key.to_string(), data));
+        }
+
+        if self.config.cache_enabled {
+            let mut cache = self.cache.lock().unwrap();
+            cache.extend(serialized.iter().cloned());
+        }
+
+        self.backend.put_many(serialized).await.map_err(|e| e.to_string())
+    }
+
+    /// Load data from persistence layer
+    pub async fn load<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<T, String> {
+        debug!("[ericadamsai] Loading data with key: {}", key);
+
+        // Check cache first
+        if self.config.cache_enabled {
+            if let Ok(cache) = self.cache.lock() {
+                if let Some(data) = cache.get(key) {
+                    return serde_json::from_slice(data)
+                        .map_err(|e| format!("Deserialization error: {}", e));
+                }
+            }
+        }
+
+        let data = self
+            .backend
+            .get(key)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No data stored under key: {}", key))?;
+        serde_json::from_slice(&data)
+            .map_err(|e| format!("Deserialization error: {}", e))
+    }
+
+    /// Delete data from persistence layer
+    pub async fn delete(&self, key: &str) -> Result<(), String> {
+        debug!("[ericadamsai] Deleting data with key: {}", key);
+
+        // Remove from cache
+        if self.config.cache_enabled {
+            if let Ok(mut cache) = self.cache.lock() {
+                cache.remove(key);
+            }
+        }
+
+        self.backend.delete(key).await.map_err(|e| e.to_string())
+    }
+
+    /// List stored keys starting with a prefix, sorted
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        self.backend.list(prefix).await.map_err(|e| e.to_string())
+    }
+
+    /// Check whether data is stored under a key
+    pub async fn exists(&self, key: &str) -> Result<bool, String> {
+        if self.config.cache_enabled {
+            if let Ok(cache) = self.cache.lock() {
+                if cache.contains_key(key) {
+                    return Ok(true);
+                }
+            }
+        }
+        self.backend.exists(key).await.map_err(|e| e.to_string())
+    }
+
+    /// Clear all cached data
+    pub fn clear_cache(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+            info!("[ericadamsai] Cache cleared");
+        }
+    }
+}
+
+/// Empty in-memory store for tests of the modules that persist through a
+/// [`DataStore`]
+#[cfg(test)]
+pub(crate) fn memory_store() -> std::sync::Arc<DataStore> {
+    let config = PersistenceConfig {
+        backend: PersistenceBackend::Memory,
+        connection_string: String::new(),
+        cache_enabled: false,
+        compression: false,
+        options: HashMap::new(),
+    };
+    std::sync::Arc::new(DataStore::with_backend(config, Box::new(MemoryBackend::new())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(backend: PersistenceBackend) -> PersistenceConfig {
+        PersistenceConfig {
+            backend,
+            connection_string: "./data".to_string(),
+            cache_enabled: true,
+            compression: false,
+            options: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_persistence_config() {
+        let store = DataStore::new(config(PersistenceBackend::FileSystem)).unwrap();
+        assert!(store.config.cache_enabled);
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_round_trip() {
+        let store = DataStore::new(config(PersistenceBackend::Memory)).unwrap();
+        store.save("runs/1", &serde_json::json!({"ok": true})).await.unwrap();
+        store.save_many(&[("runs/2", 2), ("other", 3)]).await.unwrap();
+        store.clear_cache();
+
+        let loaded: serde_json::Value = store.load("runs/1").await.unwrap();
+        assert_eq!(loaded, serde_json::json!({"ok": true}));
+        assert_eq!(store.list("runs/").await.unwrap(), vec!["runs/1", "runs/2"]);
+        assert!(store.exists("other").await.unwrap());
+
+        store.delete("other").await.unwrap();
+        assert!(!store.exists("other").await.unwrap());
+        assert!(store.load::<i32>("other").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_plugs_in_custom_backends() {
+        let shared = MemoryBackend::new();
+        let mut registry = BackendRegistry::default();
+        let backend = shared.clone();
+        registry.register("shared", move |_| Ok(Box::new(backend.clone())));
+        let mut expected = vec!["filesystem", "memory", "shared"];
+        #[cfg(feature = "lmdb")]
+        expected.push("lmdb");
+        #[cfg(feature = "postgres")]
+        expected.push("postgresql");
+        #[cfg(feature = "redis")]
+        expected.push("redis");
+        #[cfg(feature = "s3")]
+        expected.push("s3");
+        expected.sort();
+        assert_eq!(registry.names(), expected);
+
+        let custom = PersistenceBackend::Custom("shared".to_string());
+        let store = DataStore::with_registry(config(custom), &registry).unwrap();
+        store.save("key", &1).await.unwrap();
+        assert_eq!(shared.len(), 1);
+
+        let error = DataStore::with_registry(config(PersistenceBackend::Custom("tape".to_string())), &registry)
+            .err()
+            .unwrap();
+        assert_eq!(error, "no storage backend registered as tape");
+    }
+}
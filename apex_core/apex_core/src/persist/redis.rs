@@ -0,0 +1,310 @@
+//! Redis Backend - ericadamsai watermark
+//! Stores DataStore values in Redis under a per-namespace key prefix
+//!
+//! The integration test needs a running server, such as the `redis`
+//! service of docker-compose.yml:
+//!
+//! ```sh
+//! docker compose up -d redis
+//! REDIS_URL=redis://127.0.0.1:6379 cargo test --features redis -- --ignored redis
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+use async_trait::async_trait;
+use ::redis::aio::ConnectionManager;
+use ::redis::{AsyncCommands, Client, Cmd, RedisError, RedisResult};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use super::backend::{StorageBackend, StorageError};
+use super::PersistenceConfig;
+
+/// Longest wait between retries of a command
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Redis backend settings
+///
+/// Read from `PersistenceConfig.options` by [`RedisConfig::from_config`]:
+/// `namespace`, `ttl_secs` and `max_retries`.
+#[derive(Clone, Debug)]
+pub struct RedisConfig {
+    /// Server URL, such as `redis://127.0.0.1:6379/0`
+    pub url: String,
+    /// Every key is stored as `<namespace>:<key>`
+    pub namespace: String,
+    /// Expiry of written values; `None` keeps them until deleted
+    pub ttl: Option<Duration>,
+    /// Times a command is retried after the connection drops
+    pub max_retries: usize,
+}
+
+impl RedisConfig {
+    /// Settings for a server URL with the default namespace `apex`
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            namespace: "apex".to_string(),
+            ttl: None,
+            max_retries: 3,
+        }
+    }
+
+    /// Settings from a persistence configuration, whose connection string
+    /// is the server URL
+    pub fn from_config(config: &PersistenceConfig) -> Result<Self, StorageError> {
+        let mut redis = Self::new(&config.connection_string);
+        let number = |key: &str, value: &String| {
+            value
+                .parse::<u64>()
+                .map_err(|_| StorageError::InvalidConfig(format!("redis option `{}` must be a number", key)))
+        };
+        for (key, value) in &config.options {
+            match key.as_str() {
+                "namespace" => redis.namespace = value.clone(),
+                "ttl_secs" => redis.ttl = Some(Duration::from_secs(number(key, value)?)),
+                "max_retries" => redis.max_retries = number(key, value)? as usize,
+                _ => return Err(StorageError::InvalidConfig(format!("unknown redis option `{}`", key))),
+            }
+        }
+        Ok(redis)
+    }
+}
+
+/// Storage backend on a Redis server
+///
+/// The connection is opened on first use and re-established when it
+/// drops; commands that fail because of a dropped connection are retried
+/// up to `max_retries` times with exponential backoff.
+pub struct RedisBackend {
+    config: RedisConfig,
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisBackend {
+    /// Create a backend; no connection is made until the first command
+    pub fn new(config: RedisConfig) -> Result<Self, StorageError> {
+        let client = Client::open(config.url.as_str())
+            .map_err(|e| StorageError::InvalidConfig(format!("invalid redis URL: {}", e)))?;
+        info!("[ericadamsai] Redis backend for namespace {}", config.namespace);
+        Ok(Self {
+            config,
+            client,
+            connection: OnceCell::new(),
+        })
+    }
+
+    /// Create a backend from a persistence configuration
+    pub fn from_config(config: &PersistenceConfig) -> Result<Self, StorageError> {
+        Self::new(RedisConfig::from_config(config)?)
+    }
+
+    /// Redis key of a DataStore key
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.config.namespace, key)
+    }
+
+    /// `SET` command for a value, with the configured expiry
+    fn set(&self, key: &str, value: Vec<u8>) -> Cmd {
+        let mut cmd = ::redis::cmd("SET");
+        cmd.arg(self.key(key)).arg(value);
+        if let Some(ttl) = self.config.ttl {
+            cmd.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        cmd
+    }
+
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?;
+        Ok(connection.clone())
+    }
+
+    /// Run a command, retrying it while the connection is down
+    async fn run<T, F, Fut>(&self, command: F) -> Result<T, StorageError>
+    where
+        F: Fn(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match self.connection().await {
+                Ok(connection) => command(connection).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.config.max_retries && is_transient(&e) => {
+                    attempt += 1;
+                    warn!(
+                        "[ericadamsai] Redis command failed, retrying ({}/{}): {}",
+                        attempt, self.config.max_retries, e
+                    );
+                    tokio::time::sleep(retry_backoff(attempt)).await;
+                }
+                Err(e) => return Err(StorageError::Backend(e.to_string())),
+            }
+        }
+    }
+}
+
+/// Wait before the `attempt`-th retry: 100ms, doubling up to
+/// `MAX_RETRY_BACKOFF`
+fn retry_backoff(attempt: usize) -> Duration {
+    let millis = 50u64 << attempt.min(16);
+    Duration::from_millis(millis).min(MAX_RETRY_BACKOFF)
+}
+
+/// Whether an error means the connection rather than the command failed
+fn is_transient(error: &RedisError) -> bool {
+    error.is_connection_dropped() || error.is_connection_refusal() || error.is_io_error() || error.is_timeout()
+}
+
+/// Escape the characters `SCAN MATCH` treats as glob syntax
+fn escape_glob(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl StorageBackend for RedisBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let key = self.key(key);
+        self.run(|mut connection| {
+            let key = key.clone();
+            async move { connection.get(key).await }
+        })
+        .await
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<(), StorageError> {
+        let cmd = self.set(key, value);
+        self.run(|mut connection| {
+            let cmd = cmd.clone();
+            async move { cmd.query_async(&mut connection).await }
+        })
+        .await
+    }
+
+    /// Writes every value in one atomic pipeline
+    async fn put_many(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), StorageError> {
+        let mut pipeline = ::redis::pipe();
+        pipeline.atomic();
+        for (key, value) in entries {
+            pipeline.add_command(self.set(&key, value)).ignore();
+        }
+        self.run(|mut connection| {
+            let pipeline = pipeline.clone();
+            async move { pipeline.query_async(&mut connection).await }
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let key = self.key(key);
+        self.run(|mut connection| {
+            let key = key.clone();
+            async move { connection.del(key).await }
+        })
+        .await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let pattern = format!("{}*", escape_glob(&self.key(prefix)));
+        let namespace = format!("{}:", self.config.namespace);
+        let mut keys: Vec<String> = self
+            .run(|mut connection| {
+                let pattern = pattern.clone();
+                async move {
+                    let mut keys = Vec::new();
+                    let mut iter = connection.scan_match::<_, String>(pattern).await?;
+                    while let Some(key) = iter.next_item().await {
+                        keys.push(key);
+                    }
+                    Ok(keys)
+                }
+            })
+            .await?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&namespace).map(str::to_string))
+            .collect();
+        // SCAN may return a key more than once
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        let key = self.key(key);
+        self.run(|mut connection| {
+            let key = key.clone();
+            async move { connection.exists(key).await }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::PersistenceBackend;
+    use std::collections::HashMap;
+
+    fn config(options: &[(&str, &str)]) -> PersistenceConfig {
+        PersistenceConfig {
+            backend: PersistenceBackend::Redis,
+            connection_string: std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+            cache_enabled: false,
+            compression: false,
+            options: options.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_config_and_keys() {
+        let backend = RedisBackend::from_config(&config(&[("namespace", "tenant-a"), ("ttl_secs", "60")])).unwrap();
+        assert_eq!(backend.key("engines/main/state"), "tenant-a:engines/main/state");
+        assert_eq!(backend.config.ttl, Some(Duration::from_secs(60)));
+        assert_eq!(escape_glob("runs/[1]*"), "runs/\\[1\\]\\*");
+        assert_eq!(retry_backoff(1), Duration::from_millis(100));
+        assert_eq!(retry_backoff(64), MAX_RETRY_BACKOFF);
+        assert!(matches!(
+            RedisConfig::from_config(&config(&[("ttl", "60")])),
+            Err(StorageError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a redis-server at REDIS_URL"]
+    async fn test_redis_round_trip() {
+        let namespace = format!("apex-test-{}", uuid::Uuid::new_v4());
+        let backend = RedisBackend::from_config(&config(&[("namespace", &namespace), ("ttl_secs", "60")])).unwrap();
+
+        backend.put("runs/1", b"one".to_vec()).await.unwrap();
+        backend
+            .put_many(vec![("runs/2".to_string(), b"two".to_vec()), ("other".to_string(), b"x".to_vec())])
+            .await
+            .unwrap();
+        assert_eq!(backend.get("runs/2").await.unwrap(), Some(b"two".to_vec()));
+        assert_eq!(backend.list("runs/").await.unwrap(), vec!["runs/1", "runs/2"]);
+        assert!(backend.exists("other").await.unwrap());
+
+        let mut connection = backend.connection().await.unwrap();
+        let ttl: i64 = connection.ttl(backend.key("runs/1")).await.unwrap();
+        assert!(ttl > 0 && ttl <= 60);
+
+        for key in ["runs/1", "runs/2", "other"] {
+            backend.delete(key).await.unwrap();
+        }
+        assert_eq!(backend.list("").await.unwrap(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,499 @@
+//! S3 Backend - ericadamsai watermark
+//! Stores DataStore values as objects in any S3-compatible bucket
+//!
+//! Large values are sent as multipart uploads and writes can be made
+//! conditional on an object's ETag. The integration test needs a running
+//! endpoint, such as the `minio` service of docker-compose.yml, which takes
+//! its root credentials from the same variables as the backend:
+//!
+//! ```sh
+//! export AWS_ACCESS_KEY_ID=apex AWS_SECRET_ACCESS_KEY=<at least 8 characters>
+//! docker compose up -d minio
+//! S3_ENDPOINT=http://127.0.0.1:9000 cargo test --features s3 -- --ignored s3
+//! ```
+
+use std::fmt;
+use async_trait::async_trait;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::config::{
+    BehaviorVersion, Credentials, Region, RequestChecksumCalculation, ResponseChecksumValidation,
+};
+use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{BucketLocationConstraint, CompletedMultipartUpload, CompletedPart, CreateBucketConfiguration};
+use aws_sdk_s3::Client;
+use tracing::{info, debug, warn};
+
+use super::backend::{StorageBackend, StorageError};
+use super::PersistenceConfig;
+
+const MIB: usize = 1024 * 1024;
+
+/// S3 backend settings
+///
+/// Read from `PersistenceConfig.options` by [`S3Config::from_config`]:
+/// `bucket`, `region`, `namespace`, `access_key_id`, `secret_access_key`,
+/// `path_style`, `multipart_threshold_mb` and `part_size_mb`.
+#[derive(Clone)]
+pub struct S3Config {
+    /// Endpoint URL, such as `http://127.0.0.1:9000`; `None` uses AWS
+    pub endpoint: Option<String>,
+    /// Bucket holding the objects
+    pub bucket: String,
+    /// Region requests are signed for
+    pub region: String,
+    /// Every key is stored as `<namespace>/<key>`
+    pub namespace: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Address the bucket in the path rather than the host name, as most
+    /// self-hosted endpoints expect
+    pub path_style: bool,
+    /// Values larger than this many bytes are sent as multipart uploads
+    pub multipart_threshold: usize,
+    /// Size of each part of a multipart upload, at least 5 MiB
+    pub part_size: usize,
+}
+
+impl S3Config {
+    /// Settings for a bucket behind an endpoint, with the default namespace
+    /// `apex` and credentials from `AWS_ACCESS_KEY_ID` and
+    /// `AWS_SECRET_ACCESS_KEY`
+    pub fn new(endpoint: &str, bucket: &str) -> Self {
+        let endpoint = (!endpoint.is_empty()).then(|| endpoint.to_string());
+        Self {
+            path_style: endpoint.is_some(),
+            endpoint,
+            bucket: bucket.to_string(),
+            region: "us-east-1".to_string(),
+            namespace: "apex".to_string(),
+            access_key_id: std::env::var("AWS_ACCESS_KEY_ID").unwrap_or_default(),
+            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default(),
+            multipart_threshold: 8 * MIB,
+            part_size: 8 * MIB,
+        }
+    }
+
+    /// Settings from a persistence configuration, whose connection string
+    /// is the endpoint URL; an empty one selects AWS
+    pub fn from_config(config: &PersistenceConfig) -> Result<Self, StorageError> {
+        let mut s3 = Self::new(&config.connection_string, "");
+        let invalid = |key: &str, expected: &str| {
+            StorageError::InvalidConfig(format!("s3 option `{}` must be {}", key, expected))
+        };
+        for (key, value) in &config.options {
+            match key.as_str() {
+                "bucket" => s3.bucket = value.clone(),
+                "region" => s3.region = value.clone(),
+                "namespace" => s3.namespace = value.clone(),
+                "access_key_id" => s3.access_key_id = value.clone(),
+                "secret_access_key" => s3.secret_access_key = value.clone(),
+                "path_style" => s3.path_style = value.parse().map_err(|_| invalid(key, "true or false"))?,
+                "multipart_threshold_mb" => {
+                    s3.multipart_threshold = value.parse::<usize>().map_err(|_| invalid(key, "a number"))? * MIB;
+                }
+                "part_size_mb" => s3.part_size = value.parse::<usize>().map_err(|_| invalid(key, "a number"))? * MIB,
+                _ => return Err(StorageError::InvalidConfig(format!("unknown s3 option `{}`", key))),
+            }
+        }
+        Ok(s3)
+    }
+}
+
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("namespace", &self.namespace)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .field("path_style", &self.path_style)
+            .field("multipart_threshold", &self.multipart_threshold)
+            .field("part_size", &self.part_size)
+            .finish()
+    }
+}
+
+/// Precondition of a conditional write, see [`S3Backend::put_if`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WriteCondition {
+    /// Only create the object; fail if the key already holds one
+    IfAbsent,
+    /// Only replace the object while its ETag is still this one
+    IfMatch(String),
+}
+
+/// Storage backend on an S3-compatible bucket
+///
+/// Each value is one object. Values above `multipart_threshold` are split
+/// into `part_size` parts, and an upload that fails part-way is aborted so
+/// no orphaned parts are left behind. S3 has no batch write, so `put_many`
+/// writes one object after the other.
+pub struct S3Backend {
+    config: S3Config,
+    client: Client,
+}
+
+impl S3Backend {
+    /// Create a backend; no request is made until the first operation
+    pub fn new(config: S3Config) -> Result<Self, StorageError> {
+        if config.bucket.is_empty() {
+            return Err(StorageError::InvalidConfig("s3 option `bucket` is required".to_string()));
+        }
+        if config.access_key_id.is_empty() || config.secret_access_key.is_empty() {
+            return Err(StorageError::InvalidConfig("s3 credentials are missing".to_string()));
+        }
+        if config.part_size < 5 * MIB {
+            return Err(StorageError::InvalidConfig("s3 parts must be at least 5 MiB".to_string()));
+        }
+
+        let credentials = Credentials::new(&config.access_key_id, &config.secret_access_key, None, None, "apex");
+        let mut builder = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region.clone()))
+            .credentials_provider(credentials)
+            .force_path_style(config.path_style)
+            // Not every S3-compatible endpoint understands the newer checksums
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .response_checksum_validation(ResponseChecksumValidation::WhenRequired);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.endpoint_url(endpoint);
+        }
+        info!("[ericadamsai] S3 backend for bucket {} and namespace {}", config.bucket, config.namespace);
+        Ok(Self {
+            client: Client::from_conf(builder.build()),
+            config,
+        })
+    }
+
+    /// Create a backend from a persistence configuration
+    pub fn from_config(config: &PersistenceConfig) -> Result<Self, StorageError> {
+        Self::new(S3Config::from_config(config)?)
+    }
+
+    /// Create the bucket unless it already exists
+    pub async fn ensure_bucket(&self) -> Result<(), StorageError> {
+        let mut request = self.client.create_bucket().bucket(&self.config.bucket);
+        if self.config.region != "us-east-1" {
+            request = request.create_bucket_configuration(
+                CreateBucketConfiguration::builder()
+                    .location_constraint(BucketLocationConstraint::from(self.config.region.as_str()))
+                    .build(),
+            );
+        }
+        match request.send().await {
+            Ok(_) => {
+                info!("[ericadamsai] Created bucket {}", self.config.bucket);
+                Ok(())
+            }
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_bucket_already_owned_by_you()) => Ok(()),
+            Err(e) => Err(s3_error(e)),
+        }
+    }
+
+    /// Value stored under a key together with its ETag, if any
+    pub async fn get_with_etag(&self, key: &str) -> Result<Option<(Vec<u8>, String)>, StorageError> {
+        let output = match self.client.get_object().bucket(&self.config.bucket).key(self.key(key)).send().await {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(s3_error(e)),
+        };
+        let etag = output.e_tag().unwrap_or_default().to_string();
+        let body = output
+            .body
+            .collect()
+            .await
+            .map_err(|e| StorageError::Backend(format!("failed to read object {}: {}", key, e)))?;
+        Ok(Some((body.into_bytes().to_vec(), etag)))
+    }
+
+    /// Store a value only if `condition` holds for the object currently
+    /// stored under the key, and return the new ETag
+    ///
+    /// Fails with [`StorageError::PreconditionFailed`] when another writer
+    /// got there first; reload the value and retry to resolve the conflict.
+    pub async fn put_if(&self, key: &str, value: Vec<u8>, condition: WriteCondition) -> Result<String, StorageError> {
+        self.upload(key, value, Some(&condition)).await
+    }
+
+    /// Object key of a DataStore key
+    fn key(&self, key: &str) -> String {
+        format!("{}/{}", self.config.namespace, key)
+    }
+
+    async fn upload(&self, key: &str, value: Vec<u8>, condition: Option<&WriteCondition>) -> Result<String, StorageError> {
+        if value.len() > self.config.multipart_threshold {
+            return self.upload_multipart(key, value, condition).await;
+        }
+        let mut request = self
+            .client
+            .put_object()
+            .bucket(&self.config.bucket)
+            .key(self.key(key))
+            .body(ByteStream::from(value));
+        match condition {
+            Some(WriteCondition::IfAbsent) => request = request.if_none_match("*"),
+            Some(WriteCondition::IfMatch(etag)) => request = request.if_match(etag),
+            None => {}
+        }
+        let output = request.send().await.map_err(|e| write_error(key, e))?;
+        Ok(output.e_tag().unwrap_or_default().to_string())
+    }
+
+    async fn upload_multipart(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        condition: Option<&WriteCondition>,
+    ) -> Result<String, StorageError> {
+        let object = self.key(key);
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.config.bucket)
+            .key(&object)
+            .send()
+            .await
+            .map_err(s3_error)?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| StorageError::Backend(format!("no upload id for object {}", object)))?
+            .to_string();
+
+        let result = self.upload_parts(key, &object, &upload_id, &value, condition).await;
+        if result.is_err() {
+            let abort = self
+                .client
+                .abort_multipart_upload()
+                .bucket(&self.config.bucket)
+                .key(&object)
+                .upload_id(&upload_id)
+                .send()
+                .await;
+            if let Err(e) = abort {
+                warn!("[ericadamsai] Failed to abort upload {} of {}: {}", upload_id, object, DisplayErrorContext(e));
+            }
+        }
+        result
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        object: &str,
+        upload_id: &str,
+        value: &[u8],
+        condition: Option<&WriteCondition>,
+    ) -> Result<String, StorageError> {
+        let mut parts = Vec::new();
+        for (index, chunk) in value.chunks(self.config.part_size).enumerate() {
+            let part_number = index as i32 + 1;
+            let part = self
+                .client
+                .upload_part()
+                .bucket(&self.config.bucket)
+                .key(object)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(chunk.to_vec()))
+                .send()
+                .await
+                .map_err(s3_error)?;
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(part.e_tag().map(str::to_string))
+                    .build(),
+            );
+        }
+        debug!("[ericadamsai] Uploaded {} parts of {}", parts.len(), object);
+
+        let mut request = self
+            .client
+            .complete_multipart_upload()
+            .bucket(&self.config.bucket)
+            .key(object)
+            .upload_id(upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build());
+        match condition {
+            Some(WriteCondition::IfAbsent) => request = request.if_none_match("*"),
+            Some(WriteCondition::IfMatch(etag)) => request = request.if_match(etag),
+            None => {}
+        }
+        let output = request.send().await.map_err(|e| write_error(key, e))?;
+        Ok(output.e_tag().unwrap_or_default().to_string())
+    }
+}
+
+/// HTTP status of a failed request, if a response was received
+fn status<E>(error: &SdkError<E, HttpResponse>) -> Option<u16> {
+    error.raw_response().map(|response| response.status().as_u16())
+}
+
+fn s3_error<E: std::error::Error + 'static>(error: SdkError<E, HttpResponse>) -> StorageError {
+    StorageError::Backend(DisplayErrorContext(error).to_string())
+}
+
+/// Error of a write, telling failed preconditions apart
+fn write_error<E: std::error::Error + 'static>(key: &str, error: SdkError<E, HttpResponse>) -> StorageError {
+    // 409 is returned when a concurrent conditional write to the key is in flight
+    match status(&error) {
+        Some(412) | Some(409) => StorageError::PreconditionFailed(key.to_string()),
+        _ => s3_error(error),
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Backend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.get_with_etag(key).await?.map(|(value, _)| value))
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<(), StorageError> {
+        self.upload(key, value, None).await.map(|_| ())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(&self.config.bucket)
+            .key(self.key(key))
+            .send()
+            .await
+            .map_err(s3_error)?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let namespace = format!("{}/", self.config.namespace);
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.config.bucket)
+            .prefix(self.key(prefix))
+            .into_paginator()
+            .send();
+        let mut keys = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(s3_error)?;
+            keys.extend(
+                page.contents()
+                    .iter()
+                    .filter_map(|object| object.key()?.strip_prefix(&namespace).map(str::to_string)),
+            );
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        match self.client.head_object().bucket(&self.config.bucket).key(self.key(key)).send().await {
+            Ok(_) => Ok(true),
+            // A HEAD response has no body to tell a missing key from a
+            // missing bucket, so check the bucket before reporting the key absent.
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {
+                self.client
+                    .head_bucket()
+                    .bucket(&self.config.bucket)
+                    .send()
+                    .await
+                    .map_err(s3_error)?;
+                Ok(false)
+            }
+            Err(e) => Err(s3_error(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::PersistenceBackend;
+
+    fn config(options: &[(&str, &str)]) -> PersistenceConfig {
+        PersistenceConfig {
+            backend: PersistenceBackend::S3,
+            connection_string: std::env::var("S3_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:9000".to_string()),
+            cache_enabled: false,
+            compression: false,
+            options: options.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_config_and_keys() {
+        let backend = S3Backend::from_config(&config(&[
+            ("bucket", "artifacts"),
+            ("namespace", "tenant-a"),
+            ("access_key_id", "apex"),
+            ("secret_access_key", "apex-secret"),
+            ("part_size_mb", "16"),
+        ]))
+        .unwrap();
+        assert_eq!(backend.key("graph-runs/1/checkpoint"), "tenant-a/graph-runs/1/checkpoint");
+        assert!(backend.config.path_style);
+        assert_eq!(backend.config.part_size, 16 * MIB);
+        assert!(!format!("{:?}", backend.config).contains("apex-secret"));
+
+        assert!(matches!(
+            S3Backend::from_config(&config(&[
+                ("bucket", "artifacts"),
+                ("access_key_id", "apex"),
+                ("secret_access_key", "apex-secret"),
+                ("part_size_mb", "1"),
+            ])),
+            Err(StorageError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            S3Config::from_config(&config(&[("path_style", "maybe")])),
+            Err(StorageError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs an S3-compatible endpoint at S3_ENDPOINT"]
+    async fn test_s3_round_trip() {
+        let namespace = format!("apex-test-{}", uuid::Uuid::new_v4());
+        let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "apex-test".to_string());
+        let backend = S3Backend::from_config(&config(&[
+            ("bucket", &bucket),
+            ("namespace", &namespace),
+            ("multipart_threshold_mb", "5"),
+            ("part_size_mb", "5"),
+        ]))
+        .unwrap();
+        backend.ensure_bucket().await.unwrap();
+
+        let artifact: Vec<u8> = (0..11 * MIB).map(|i| (i % 251) as u8).collect();
+        backend.put("runs/1/artifact", artifact.clone()).await.unwrap();
+        backend.put("runs/1/checkpoint", b"one".to_vec()).await.unwrap();
+        backend.put("other", b"x".to_vec()).await.unwrap();
+        assert_eq!(backend.get("runs/1/artifact").await.unwrap(), Some(artifact));
+        assert_eq!(backend.list("runs/").await.unwrap(), vec!["runs/1/artifact", "runs/1/checkpoint"]);
+        assert!(backend.exists("other").await.unwrap());
+        assert_eq!(backend.get("missing").await.unwrap(), None);
+
+        let (_, etag) = backend.get_with_etag("runs/1/checkpoint").await.unwrap().unwrap();
+        let updated = backend
+            .put_if("runs/1/checkpoint", b"two".to_vec(), WriteCondition::IfMatch(etag.clone()))
+            .await
+            .unwrap();
+        assert_ne!(updated, etag);
+        assert!(matches!(
+            backend.put_if("runs/1/checkpoint", b"three".to_vec(), WriteCondition::IfMatch(etag)).await,
+            Err(StorageError::PreconditionFailed(_))
+        ));
+        assert!(matches!(
+            backend.put_if("other", b"y".to_vec(), WriteCondition::IfAbsent).await,
+            Err(StorageError::PreconditionFailed(_))
+        ));
+        assert_eq!(backend.get("runs/1/checkpoint").await.unwrap(), Some(b"two".to_vec()));
+
+        for key in ["runs/1/artifact", "runs/1/checkpoint", "other"] {
+            backend.delete(key).await.unwrap();
+        }
+        assert_eq!(backend.list("").await.unwrap(), Vec::<String>::new());
+    }
+}
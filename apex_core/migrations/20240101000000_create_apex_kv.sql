@@ -0,0 +1,12 @@
+-- DataStore key/value table - ericadamsai watermark
+CREATE TABLE IF NOT EXISTS apex_kv (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value BYTEA NOT NULL,
+    -- Copy of the value for JSONB queries, when it is valid JSON
+    document JSONB,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (namespace, key)
+);
+
+CREATE INDEX IF NOT EXISTS apex_kv_document_idx ON apex_kv USING GIN (document jsonb_path_ops);